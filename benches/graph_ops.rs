@@ -0,0 +1,66 @@
+//! Timings of `Graph::add_edge` and BFS, with and without a live snapshot.
+//! Run with `cargo bench --bench graph_ops`.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use graphstuffs::algos::bfs::BfsExt;
+use graphstuffs::core::graph::Graph;
+
+const NODES: u32 = 50_000;
+const EDGES: usize = 250_000;
+const ROUNDS: usize = 10;
+
+/// Deterministic pseudo-random edge list, so runs compare like for like.
+fn edge_list() -> Vec<(u32, u32)> {
+    let mut state: u64 = 0x9e37_79b9_7f4a_7c15;
+    let mut next = move || {
+        state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) as u32 % NODES
+    };
+    (0..EDGES).map(|_| (next(), next())).collect()
+}
+
+fn build(edges: &[(u32, u32)]) -> Graph<u32> {
+    let mut graph = Graph::new(false);
+    for &(u, v) in edges {
+        graph.add_edge(u, v).unwrap();
+    }
+    graph
+}
+
+/// Best of `ROUNDS` runs of `f`.
+fn best<T>(mut f: impl FnMut() -> T) -> Duration {
+    (0..ROUNDS)
+        .map(|_| {
+            let start = Instant::now();
+            black_box(f());
+            start.elapsed()
+        })
+        .min()
+        .unwrap()
+}
+
+fn report(name: &str, elapsed: Duration, ops: usize) {
+    println!("{name:<28} {:>9.2} ms  {:>7.1} ns/op", elapsed.as_secs_f64() * 1e3, elapsed.as_nanos() as f64 / ops as f64);
+}
+
+fn main() {
+    let edges = edge_list();
+    report("add_edge", best(|| build(&edges)), EDGES);
+
+    let mut graph = build(&edges);
+    report(
+        "add_edge with snapshot",
+        best(|| {
+            let snapshot = graph.snapshot();
+            for &(u, v) in &edges[..EDGES / 10] {
+                graph.add_edge(v, u).unwrap();
+            }
+            snapshot.version()
+        }),
+        EDGES / 10,
+    );
+
+    report("bfs", best(|| graph.bfs(0).unwrap()), graph.node_count());
+}
@@ -1,109 +1,362 @@
-use std::collections::{HashSet,HashMap, VecDeque};
-use crate::core::graph::Graph;
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use crate::algos::visited::VisitMap;
+use crate::algos::visitor::{BfsVisitor, Control};
+use crate::core::edge::EdgeRef;
+use crate::core::error::GraphError;
+use crate::core::traits::{ensure_node, EdgeOf, GraphBase};
+use crate::core::views::Reversed;
+
+
+/// Breadth-first traversals, available on every `GraphBase`.
+///
+/// Every traversal fails with `GraphError::NodeNotFound` if `start` (or
+/// `end`) is not in the graph. All of them run on `bfs_visit`.
+pub trait BfsExt: GraphBase {
+    fn bfs(&self, start: Self::Node) -> Result<Vec<Self::Node>, GraphError> {
+        let mut order = Discovered(Vec::new());
+        self.bfs_visit(start, &mut order)?;
+        Ok(order.0)
+    }
 
+    /// Fewest-hop path from `start` to `end`, or `Ok(None)` if `end` is
+    /// unreachable.
+    fn shortest_path_bfs(&self, start: Self::Node, end: Self::Node) -> Result<Option<Vec<Self::Node>>, GraphError> {
+        ensure_node(self, &end)?;
+        let mut search = PathSearch { end, parent: HashMap::new(), found: false };
+        self.bfs_visit(start.clone(), &mut search)?;
+        Ok(search.found.then(|| reconstruct_path(&search.parent, start, search.end)))
+    }
 
-impl Graph{
-    pub fn bfs(&self, start: i32) -> Vec<i32> {
-    let mut visited = HashSet::new();
-    let mut queue  = VecDeque::new();
-    let mut result = Vec::new();
 
-    queue.push_back(start);
-    visited.insert(start);
+    fn bfs_distances(&self, start: Self::Node) -> Result<HashMap<Self::Node,i32>, GraphError>{
+        let mut distances = Distances(HashMap::new());
+        self.bfs_visit(start, &mut distances)?;
+        Ok(distances.0)
+    }
 
-        while let Some(node) = queue.pop_front(){
-            result.push(node);
+    /// BFS that only crosses edges for which `follow` returns `true`.
+    /// `follow` sees each edge before its target is visited, so it can
+    /// consult node or edge payloads by `edge.target` and `edge.id`.
+    fn bfs_filtered<F>(&self, start: Self::Node, follow: F) -> Result<Vec<Self::Node>, GraphError>
+    where
+        F: FnMut(&EdgeRef<Self::Node, Self::Weight>) -> bool,
+    {
+        let mut filtered = Filtered { follow, order: Vec::new() };
+        self.bfs_visit(start, &mut filtered)?;
+        Ok(filtered.order)
+    }
 
-            if let Some(neighbors) = self.adj_list.get(&node) {
-                for &neighbor in neighbors {
-                    if !visited.contains(&neighbor) {
-                        visited.insert(neighbor);
-                        queue.push_back(neighbor);
-                    }
-                }
-            }
-        }
-        result
+    /// The edges through which BFS from `start` discovers each node, in
+    /// discovery order.
+    fn bfs_tree_edges(&self, start: Self::Node) -> Result<Vec<EdgeOf<Self>>, GraphError> {
+        let mut tree = TreeEdges(Vec::new());
+        self.bfs_visit(start, &mut tree)?;
+        Ok(tree.0)
+    }
+
+    /// BFS that follows edges backwards, i.e. from a node to its
+    /// predecessors.
+    fn bfs_reverse(&self, start: Self::Node) -> Result<Vec<Self::Node>, GraphError> {
+        Reversed::new(self).bfs(start)
     }
 
+    /// BFS over the whole graph: each node in ascending order that no
+    /// earlier search reached becomes the root of a new tree.
+    fn bfs_forest(&self) -> BfsForest<Self::Node>
+    where
+        Self::Node: Ord,
+    {
+        let mut roots: Vec<_> = self.nodes().collect();
+        roots.sort();
+        let mut builder = BfsForestBuilder(BfsForest { roots: Vec::new(), parent: HashMap::new(), level: HashMap::new() });
+        visit_from_roots(self, roots, &mut builder);
+        builder.0
+    }
+
+    /// `Bfs` from `start`, yielding nodes on demand.
+    fn bfs_iter(&self, start: Self::Node) -> Result<Bfs<'_, Self>, GraphError> {
+        Bfs::new(self, start)
+    }
+
+    /// BFS from `start` reporting every event to `visitor`; see
+    /// `BfsVisitor` for the events and how `Prune` and `Stop` act on them.
+    /// Out-edges are examined in `out_edges` order.
+    fn bfs_visit<V>(&self, start: Self::Node, visitor: &mut V) -> Result<(), GraphError>
+    where
+        V: BfsVisitor<Self::Node, Self::Weight>,
+    {
+        ensure_node(self, &start)?;
+        visit_from_roots(self, [start], visitor);
+        Ok(())
+    }
+}
+
+impl<G: GraphBase + ?Sized> BfsExt for G {}
+
+/// Spanning forest found by `BfsExt::bfs_forest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BfsForest<N: Eq + Hash> {
+    /// The root of every tree, in the order the trees were grown.
+    pub roots: Vec<N>,
+    /// The node each non-root node was discovered from.
+    pub parent: HashMap<N, N>,
+    /// Hops from its tree's root to each node, so roots are at level 0.
+    pub level: HashMap<N, usize>,
+}
 
-    fn reconstruct_path(&self, parent: &HashMap<i32,i32>, start: i32, end: i32) -> Vec<i32> {
-        let mut path = Vec::new();
-        let mut current = end;
+/// Lazy BFS yielding nodes in the same order as `bfs`.
+///
+/// The visited set outlives each traversal: `move_to` starts a new one that
+/// skips every node already yielded, so looping it over `nodes()` walks the
+/// whole graph one component at a time. Nodes that were queued but not yet
+/// yielded when `move_to` is called are forgotten and can be reached again.
+pub struct Bfs<'a, G: GraphBase + ?Sized> {
+    graph: &'a G,
+    queue: VecDeque<G::Node>,
+    visited: VisitMap<'a, G>,
+}
 
-        while current != start {
-            path.push(current);
-            current = parent[&current];
+impl<'a, G: GraphBase + ?Sized> Bfs<'a, G> {
+    pub fn new(graph: &'a G, start: G::Node) -> Result<Self, GraphError> {
+        let mut bfs = Bfs { graph, queue: VecDeque::new(), visited: VisitMap::new(graph) };
+        bfs.move_to(start)?;
+        Ok(bfs)
+    }
 
+    /// Drops the rest of the current traversal and continues from `start`,
+    /// which yields nothing if `start` was already yielded.
+    pub fn move_to(&mut self, start: G::Node) -> Result<(), GraphError> {
+        ensure_node(self.graph, &start)?;
+        for dropped in self.queue.drain(..) {
+            self.visited.unvisit(&dropped);
+        }
+        if self.visited.visit(&start) {
+            self.queue.push_back(start);
         }
-        path.push(start);
-        path.reverse();
-        path
+        Ok(())
+    }
 
+    /// Whether `node` has been yielded or is queued to be.
+    pub fn is_visited(&self, node: &G::Node) -> bool {
+        self.visited.is_visited(node)
     }
+}
 
-    pub fn shortest_path_bfs(&self, start: i32, end: i32) -> Option<Vec<i32>> {
-        let mut visited = HashSet::new();
-        let mut queue = VecDeque::new();
-        let mut parent = HashMap::new();
+impl<G: GraphBase + ?Sized> Iterator for Bfs<'_, G> {
+    type Item = G::Node;
 
-        queue.push_back(start);
-        visited.insert(start);
+    fn next(&mut self) -> Option<G::Node> {
+        let node = self.queue.pop_front()?;
+        for neighbor in self.graph.neighbors(&node) {
+            if self.visited.visit(&neighbor) {
+                self.queue.push_back(neighbor);
+            }
+        }
+        Some(node)
+    }
+}
 
-        while let Some(node) = queue.pop_front(){
-            if node == end {
-                return Some(self.reconstruct_path(&parent, start, end));
+/// The queue loop behind every BFS. Nodes are marked visited when queued,
+/// each with its depth and whether it may be expanded. Each root not
+/// reached by an earlier one starts a new search at depth 0.
+fn visit_from_roots<G, V, I>(graph: &G, roots: I, visitor: &mut V)
+where
+    G: GraphBase + ?Sized,
+    V: BfsVisitor<G::Node, G::Weight>,
+    I: IntoIterator<Item = G::Node>,
+{
+    let mut visited = VisitMap::new(graph);
+    let mut queue = VecDeque::new();
+
+    for root in roots {
+        if !visited.visit(&root) {
+            continue;
+        }
+        match visitor.discover(&root, 0) {
+            Control::Stop => return,
+            control => queue.push_back((root, 0, control == Control::Continue)),
+        }
+        if !expand_queue(graph, &mut queue, &mut visited, visitor) {
+            return;
+        }
+    }
+}
+
+/// Runs BFS until `queue` is empty. Returns `false` if the visitor stopped
+/// it.
+fn expand_queue<G, V>(graph: &G, queue: &mut VecDeque<(G::Node, usize, bool)>, visited: &mut VisitMap<G>, visitor: &mut V) -> bool
+where
+    G: GraphBase + ?Sized,
+    V: BfsVisitor<G::Node, G::Weight>,
+{
+    let mut level = None;
+    let mut level_expands = true;
+    while let Some((node, depth, expand)) = queue.pop_front() {
+        if level != Some(depth) {
+            level = Some(depth);
+            match visitor.level(depth) {
+                Control::Stop => return false,
+                Control::Prune => level_expands = false,
+                Control::Continue => {}
             }
-            if let Some(neighbors) = self.adj_list.get(&node) {
-                for &neighbor in neighbors {
-                    if !visited.contains(&neighbor) {
-                        visited.insert(neighbor);
-                        parent.insert(neighbor, node);
-                        queue.push_back(neighbor);
-                    }
+        }
+        if expand && level_expands {
+            for edge in graph.out_edges(&node) {
+                match visitor.examine_edge(&edge) {
+                    Control::Stop => return false,
+                    Control::Prune => continue,
+                    Control::Continue => {}
+                }
+                if visited.is_visited(&edge.target) {
+                    continue;
+                }
+                match visitor.tree_edge(&edge) {
+                    Control::Stop => return false,
+                    Control::Prune => continue,
+                    Control::Continue => {}
+                }
+                let target = edge.target;
+                visited.visit(&target);
+                match visitor.discover(&target, depth + 1) {
+                    Control::Stop => return false,
+                    control => queue.push_back((target, depth + 1, control == Control::Continue)),
                 }
             }
         }
-        None
+        if visitor.finish(&node) == Control::Stop {
+            return false;
+        }
     }
+    true
+}
 
+/// Fills a `BfsForest`; a node discovered without a tree edge is a root.
+struct BfsForestBuilder<N: Eq + Hash>(BfsForest<N>);
 
-    pub fn bfs_distances(&self, start:i32) -> HashMap<i32,i32>{
-    let mut distances: HashMap<i32,i32> = HashMap::new();
-        let mut visited: HashSet<i32> = HashSet::new();
-        let mut queue = VecDeque::new();
+impl<N: Eq + Hash + Clone, W> BfsVisitor<N, W> for BfsForestBuilder<N> {
+    fn discover(&mut self, node: &N, depth: usize) -> Control {
+        if depth == 0 {
+            self.0.roots.push(node.clone());
+        }
+        self.0.level.insert(node.clone(), depth);
+        Control::Continue
+    }
 
-        queue.push_back(start);
-        visited.insert(start);
-        distances.insert(start,0);
+    fn tree_edge(&mut self, edge: &EdgeRef<N, W>) -> Control {
+        self.0.parent.insert(edge.target.clone(), edge.source.clone());
+        Control::Continue
+    }
+}
 
-        while let Some(node ) = queue.pop_front(){
-            if let Some(neighbors) = self.adj_list.get(&node) {
-                for &neighbor in neighbors {
-                    if !visited.contains(&neighbor) {
-                        visited.insert(neighbor);
-                        distances.insert(neighbor,distances[&node] + 1);
-                        queue.push_back(neighbor);
-                    }
-                }
-            }
+/// Collects nodes in discovery order.
+struct Discovered<N>(Vec<N>);
+
+impl<N: Clone, W> BfsVisitor<N, W> for Discovered<N> {
+    fn discover(&mut self, node: &N, _depth: usize) -> Control {
+        self.0.push(node.clone());
+        Control::Continue
+    }
+}
+
+/// Records each node's BFS parent and stops once `end` is discovered.
+struct PathSearch<N> {
+    end: N,
+    parent: HashMap<N, N>,
+    found: bool,
+}
+
+impl<N: Eq + Hash + Clone, W> BfsVisitor<N, W> for PathSearch<N> {
+    fn discover(&mut self, node: &N, _depth: usize) -> Control {
+        if *node == self.end {
+            self.found = true;
+            return Control::Stop;
         }
-        distances
+        Control::Continue
+    }
+
+    fn tree_edge(&mut self, edge: &EdgeRef<N, W>) -> Control {
+        self.parent.insert(edge.target.clone(), edge.source.clone());
+        Control::Continue
+    }
+}
+
+/// Hop count of every discovered node.
+struct Distances<N>(HashMap<N, i32>);
+
+impl<N: Eq + Hash + Clone, W> BfsVisitor<N, W> for Distances<N> {
+    fn discover(&mut self, node: &N, depth: usize) -> Control {
+        self.0.insert(node.clone(), depth as i32);
+        Control::Continue
+    }
+}
+
+/// Discovery order, crossing only edges `follow` accepts.
+struct Filtered<N, F> {
+    follow: F,
+    order: Vec<N>,
+}
+
+impl<N: Clone, W, F: FnMut(&EdgeRef<N, W>) -> bool> BfsVisitor<N, W> for Filtered<N, F> {
+    fn discover(&mut self, node: &N, _depth: usize) -> Control {
+        self.order.push(node.clone());
+        Control::Continue
+    }
+
+    fn tree_edge(&mut self, edge: &EdgeRef<N, W>) -> Control {
+        if (self.follow)(edge) { Control::Continue } else { Control::Prune }
+    }
+}
+
+/// The edge that discovered each node.
+struct TreeEdges<N, W>(Vec<EdgeRef<N, W>>);
+
+impl<N: Clone, W: Clone> BfsVisitor<N, W> for TreeEdges<N, W> {
+    fn tree_edge(&mut self, edge: &EdgeRef<N, W>) -> Control {
+        self.0.push(edge.clone());
+        Control::Continue
     }
 }
 
+
+/// Walks `parent` links back from `end`. Stops early if a link is missing,
+/// which can't happen for a map filled by BFS from `start`.
+fn reconstruct_path<N: Eq + Hash + Clone>(parent: &HashMap<N,N>, start: N, end: N) -> Vec<N> {
+    let mut path = Vec::new();
+    let mut current = end;
+
+    while current != start {
+        let Some(next) = parent.get(&current) else {
+            break;
+        };
+        let next = next.clone();
+        path.push(current);
+        current = next;
+
+    }
+    path.push(current);
+    path.reverse();
+    path
+
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::HashSet;
+    use crate::core::graph::Graph;
+    use crate::core::weightedgraph::WeightedGraph;
     #[test]
     fn test_bfs_single_node(){
-        let graph = Graph::new(false);
-        let res = graph.bfs(1);
+        let mut graph = Graph::new(false);
+        assert_eq!(graph.bfs(1), Err(GraphError::NodeNotFound));
+        graph.add_node(1);
+        let res = graph.bfs(1).unwrap();
         assert_eq!(res, vec![1]);
         // self loop
         let mut graph_self = Graph::new(false);
-        graph_self.add_edge(1, 1);
-        let result_self = graph_self.bfs(1);
+        graph_self.add_edge(1, 1).unwrap();
+        let result_self = graph_self.bfs(1).unwrap();
         assert_eq!(result_self, vec![1]);
 
     }
@@ -112,15 +365,15 @@ mod tests {
     fn test_bfs_linear_path() {
         let mut graph = Graph::new(false);
         // 1 - 2 - 3 - 4
-        graph.add_edge(1, 2);
-        graph.add_edge(2, 3);
-        graph.add_edge(3, 4);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(2, 3).unwrap();
+        graph.add_edge(3, 4).unwrap();
 
-        let result = graph.bfs(1);
+        let result = graph.bfs(1).unwrap();
         assert_eq!(result, vec![1, 2, 3, 4]);
 
         // Test from different starting points
-        let result_from_3 = graph.bfs(3);
+        let result_from_3 = graph.bfs(3).unwrap();
         assert_eq!(result_from_3, vec![3, 2, 4, 1]);
     }
 
@@ -133,13 +386,13 @@ mod tests {
         //     2   3
         //    /   / \
         //   4   5   6
-        graph.add_edge(1, 2);
-        graph.add_edge(1, 3);
-        graph.add_edge(2, 4);
-        graph.add_edge(3, 5);
-        graph.add_edge(3, 6);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 3).unwrap();
+        graph.add_edge(2, 4).unwrap();
+        graph.add_edge(3, 5).unwrap();
+        graph.add_edge(3, 6).unwrap();
 
-        let result = graph.bfs(1);
+        let result = graph.bfs(1).unwrap();
 
         // BFS should visit level by level
         assert_eq!(result[0], 1);          // Level 0
@@ -157,22 +410,22 @@ mod tests {
     fn test_bfs_directed_vs_undirected() {
         // Directed graph: 1 -> 2 -> 3
         let mut directed_graph = Graph::new(true);
-        directed_graph.add_edge(1, 2);
-        directed_graph.add_edge(2, 3);
+        directed_graph.add_edge(1, 2).unwrap();
+        directed_graph.add_edge(2, 3).unwrap();
 
-        let result_directed = directed_graph.bfs(1);
+        let result_directed = directed_graph.bfs(1).unwrap();
         assert_eq!(result_directed, vec![1, 2, 3]);
 
         // Starting from 3 should only reach 3
-        let result_from_3 = directed_graph.bfs(3);
+        let result_from_3 = directed_graph.bfs(3).unwrap();
         assert_eq!(result_from_3, vec![3]);
 
         // Undirected graph: 1 - 2 - 3
         let mut undirected_graph = Graph::new(false);
-        undirected_graph.add_edge(1, 2);
-        undirected_graph.add_edge(2, 3);
+        undirected_graph.add_edge(1, 2).unwrap();
+        undirected_graph.add_edge(2, 3).unwrap();
 
-        let result_undirected = undirected_graph.bfs(3);
+        let result_undirected = undirected_graph.bfs(3).unwrap();
         assert_eq!(result_undirected, vec![3, 2, 1]);
     }
 
@@ -180,12 +433,12 @@ mod tests {
     fn test_bfs_cycle() {
         let mut graph = Graph::new(false);
         // Create a cycle: 1 - 2 - 3 - 4 - 1
-        graph.add_edge(1, 2);
-        graph.add_edge(2, 3);
-        graph.add_edge(3, 4);
-        graph.add_edge(4, 1);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(2, 3).unwrap();
+        graph.add_edge(3, 4).unwrap();
+        graph.add_edge(4, 1).unwrap();
 
-        let result = graph.bfs(1);
+        let result = graph.bfs(1).unwrap();
         assert_eq!(result.len(), 4);
         assert_eq!(result[0], 1);
 
@@ -203,11 +456,11 @@ mod tests {
     fn test_bfs_disconnected_components() {
         let mut graph = Graph::new(false);
         // Two disconnected components: (1-2) and (3-4)
-        graph.add_edge(1, 2);
-        graph.add_edge(3, 4);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(3, 4).unwrap();
 
         // BFS from component 1
-        let result1 = graph.bfs(1);
+        let result1 = graph.bfs(1).unwrap();
         assert_eq!(result1.len(), 2);
         assert!(result1.contains(&1));
         assert!(result1.contains(&2));
@@ -215,7 +468,7 @@ mod tests {
         assert!(!result1.contains(&4));
 
         // BFS from component 2
-        let result2 = graph.bfs(3);
+        let result2 = graph.bfs(3).unwrap();
         assert_eq!(result2.len(), 2);
         assert!(result2.contains(&3));
         assert!(result2.contains(&4));
@@ -229,17 +482,17 @@ mod tests {
         // Complete graph K4
         for i in 1..=4 {
             for j in (i+1)..=4 {
-                graph.add_edge(i, j);
+                graph.add_edge(i, j).unwrap();
             }
         }
 
-        let result = graph.bfs(1);
+        let result = graph.bfs(1).unwrap();
         assert_eq!(result.len(), 4);
         assert_eq!(result[0], 1);
 
         // All other nodes should be at level 1 (directly connected)
         for &node in &result[1..] {
-            assert!(vec![2, 3, 4].contains(&node));
+            assert!([2, 3, 4].contains(&node));
         }
     }
 
@@ -247,14 +500,14 @@ mod tests {
     fn test_shortest_path_bfs_simple() {
         let mut graph = Graph::new(false);
         // 1 - 2 - 3 - 4
-        graph.add_edge(1, 2);
-        graph.add_edge(2, 3);
-        graph.add_edge(3, 4);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(2, 3).unwrap();
+        graph.add_edge(3, 4).unwrap();
 
-        let path = graph.shortest_path_bfs(1, 4).unwrap();
+        let path = graph.shortest_path_bfs(1, 4).unwrap().unwrap();
         assert_eq!(path, vec![1, 2, 3, 4]);
 
-        let path_reverse = graph.shortest_path_bfs(4, 1).unwrap();
+        let path_reverse = graph.shortest_path_bfs(4, 1).unwrap().unwrap();
         assert_eq!(path_reverse, vec![4, 3, 2, 1]);
     }
 
@@ -264,12 +517,12 @@ mod tests {
         //   1 --- 4
         //   |     |
         //   2 --- 3
-        graph.add_edge(1, 2);
-        graph.add_edge(1, 4);
-        graph.add_edge(2, 3);
-        graph.add_edge(3, 4);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 4).unwrap();
+        graph.add_edge(2, 3).unwrap();
+        graph.add_edge(3, 4).unwrap();
 
-        let path = graph.shortest_path_bfs(1, 3).unwrap();
+        let path = graph.shortest_path_bfs(1, 3).unwrap().unwrap();
         // Should take direct path: either 1->2->3 or 1->4->3 (both length 3)
         assert_eq!(path.len(), 3);
         assert_eq!(path[0], 1);
@@ -280,22 +533,22 @@ mod tests {
     fn test_shortest_path_bfs_no_path() {
         let mut graph = Graph::new(false);
         // Disconnected: 1-2 and 3-4
-        graph.add_edge(1, 2);
-        graph.add_edge(3, 4);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(3, 4).unwrap();
 
-        let path = graph.shortest_path_bfs(1, 3);
+        let path = graph.shortest_path_bfs(1, 3).unwrap();
         assert!(path.is_none());
 
-        let path_reverse = graph.shortest_path_bfs(4, 1);
+        let path_reverse = graph.shortest_path_bfs(4, 1).unwrap();
         assert!(path_reverse.is_none());
     }
 
     #[test]
     fn test_shortest_path_bfs_same_node() {
         let mut graph = Graph::new(false);
-        graph.add_edge(1, 2);
+        graph.add_edge(1, 2).unwrap();
 
-        let path = graph.shortest_path_bfs(1, 1).unwrap();
+        let path = graph.shortest_path_bfs(1, 1).unwrap().unwrap();
         assert_eq!(path, vec![1]);
     }
 
@@ -307,13 +560,13 @@ mod tests {
         //     2   3
         //    /   / \
         //   4   5   6
-        graph.add_edge(1, 2);
-        graph.add_edge(1, 3);
-        graph.add_edge(2, 4);
-        graph.add_edge(3, 5);
-        graph.add_edge(3, 6);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 3).unwrap();
+        graph.add_edge(2, 4).unwrap();
+        graph.add_edge(3, 5).unwrap();
+        graph.add_edge(3, 6).unwrap();
 
-        let distances = graph.bfs_distances(1);
+        let distances = graph.bfs_distances(1).unwrap();
 
         assert_eq!(distances[&1], 0);
         assert_eq!(distances[&2], 1);
@@ -328,20 +581,20 @@ mod tests {
         let mut graph = Graph::new(false);
         // Center node 1 connected to 2, 3, 4, 5
         for i in 2..=5 {
-            graph.add_edge(1, i);
+            graph.add_edge(1, i).unwrap();
         }
 
-        let result = graph.bfs(1);
+        let result = graph.bfs(1).unwrap();
         assert_eq!(result[0], 1);
         assert_eq!(result.len(), 5);
 
         // All other nodes should be at distance 1
         for &node in &result[1..] {
-            assert!(vec![2, 3, 4, 5].contains(&node));
+            assert!([2, 3, 4, 5].contains(&node));
         }
 
         // Test distances
-        let distances = graph.bfs_distances(1);
+        let distances = graph.bfs_distances(1).unwrap();
         assert_eq!(distances[&1], 0);
         for i in 2..=5 {
             assert_eq!(distances[&i], 1);
@@ -351,23 +604,23 @@ mod tests {
     #[test]
     fn test_bfs_negative_nodes() {
         let mut graph = Graph::new(false);
-        graph.add_edge(-1, -2);
-        graph.add_edge(-2, -3);
+        graph.add_edge(-1, -2).unwrap();
+        graph.add_edge(-2, -3).unwrap();
 
-        let result = graph.bfs(-1);
+        let result = graph.bfs(-1).unwrap();
         assert_eq!(result, vec![-1, -2, -3]);
 
-        let path = graph.shortest_path_bfs(-1, -3).unwrap();
+        let path = graph.shortest_path_bfs(-1, -3).unwrap().unwrap();
         assert_eq!(path, vec![-1, -2, -3]);
     }
 
     #[test]
     fn test_bfs_large_numbers() {
         let mut graph = Graph::new(false);
-        graph.add_edge(1000000, 2000000);
-        graph.add_edge(2000000, 3000000);
+        graph.add_edge(1000000, 2000000).unwrap();
+        graph.add_edge(2000000, 3000000).unwrap();
 
-        let result = graph.bfs(1000000);
+        let result = graph.bfs(1000000).unwrap();
         assert_eq!(result, vec![1000000, 2000000, 3000000]);
     }
 
@@ -380,14 +633,14 @@ mod tests {
         //  2  3  4
         //  |  |  |
         //  5  6  7
-        graph.add_edge(1, 2);
-        graph.add_edge(1, 3);
-        graph.add_edge(1, 4);
-        graph.add_edge(2, 5);
-        graph.add_edge(3, 6);
-        graph.add_edge(4, 7);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 3).unwrap();
+        graph.add_edge(1, 4).unwrap();
+        graph.add_edge(2, 5).unwrap();
+        graph.add_edge(3, 6).unwrap();
+        graph.add_edge(4, 7).unwrap();
 
-        let result = graph.bfs(1);
+        let result = graph.bfs(1).unwrap();
 
         // Level 0: node 1
         assert_eq!(result[0], 1);
@@ -407,11 +660,11 @@ mod tests {
 
         // Create a binary tree of depth 10 (1023 nodes)
         for i in 1..512 {
-            graph.add_edge(i, 2 * i);     // Left child
-            graph.add_edge(i, 2 * i + 1); // Right child
+            graph.add_edge(i, 2 * i).unwrap();     // Left child
+            graph.add_edge(i, 2 * i + 1).unwrap(); // Right child
         }
 
-        let result = graph.bfs(1);
+        let result = graph.bfs(1).unwrap();
         assert_eq!(result.len(), 1023);
         assert_eq!(result[0], 1);
 
@@ -426,12 +679,12 @@ mod tests {
     #[test]
     fn test_bfs_self_loops() {
         let mut graph = Graph::new(true);
-        graph.add_edge(1, 1); // Self loop
-        graph.add_edge(1, 2);
-        graph.add_edge(2, 2); // Another self loop
-        graph.add_edge(2, 3);
+        graph.add_edge(1, 1).unwrap(); // Self loop
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(2, 2).unwrap(); // Another self loop
+        graph.add_edge(2, 3).unwrap();
 
-        let result = graph.bfs(1);
+        let result = graph.bfs(1).unwrap();
         assert_eq!(result.len(), 3);
         assert!(result.contains(&1));
         assert!(result.contains(&2));
@@ -447,12 +700,12 @@ mod tests {
         // 2   3
         //  \ /
         //   4
-        graph.add_edge(1, 2);
-        graph.add_edge(1, 3);
-        graph.add_edge(2, 4);
-        graph.add_edge(3, 4);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 3).unwrap();
+        graph.add_edge(2, 4).unwrap();
+        graph.add_edge(3, 4).unwrap();
 
-        let path = graph.shortest_path_bfs(1, 4).unwrap();
+        let path = graph.shortest_path_bfs(1, 4).unwrap().unwrap();
         assert_eq!(path.len(), 3); // Length should be 3
         assert_eq!(path[0], 1);
         assert_eq!(path[2], 4);
@@ -464,13 +717,12 @@ mod tests {
     fn test_bfs_empty_graph_behavior() {
         let graph = Graph::new(false);
 
-        // BFS on non-existent node should just return that node
-        let result = graph.bfs(99);
-        assert_eq!(result, vec![99]);
+        // BFS on non-existent node is an error
+        assert_eq!(graph.bfs(99), Err(GraphError::NodeNotFound));
+        assert_eq!(graph.bfs_distances(99), Err(GraphError::NodeNotFound));
 
         // Shortest path in empty graph
-        let path = graph.shortest_path_bfs(1, 2);
-        assert!(path.is_none());
+        assert_eq!(graph.shortest_path_bfs(1, 2), Err(GraphError::NodeNotFound));
     }
 
     #[test]
@@ -480,15 +732,15 @@ mod tests {
         //   1 - 2 - 5
         //   |   |   |
         //   3 - 4 - 6
-        graph.add_edge(1, 2);
-        graph.add_edge(1, 3);
-        graph.add_edge(2, 4);
-        graph.add_edge(2, 5);
-        graph.add_edge(3, 4);
-        graph.add_edge(4, 6);
-        graph.add_edge(5, 6);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 3).unwrap();
+        graph.add_edge(2, 4).unwrap();
+        graph.add_edge(2, 5).unwrap();
+        graph.add_edge(3, 4).unwrap();
+        graph.add_edge(4, 6).unwrap();
+        graph.add_edge(5, 6).unwrap();
 
-        let distances = graph.bfs_distances(1);
+        let distances = graph.bfs_distances(1).unwrap();
 
         // Verify expected distances
         assert_eq!(distances[&1], 0);
@@ -499,7 +751,233 @@ mod tests {
         assert_eq!(distances[&6], 3);
 
         // Verify shortest paths
-        let path_to_6 = graph.shortest_path_bfs(1, 6).unwrap();
+        let path_to_6 = graph.shortest_path_bfs(1, 6).unwrap().unwrap();
         assert_eq!(path_to_6.len(), 4); // Should be length 4 (distance 3 + 1)
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_bfs_string_nodes() {
+        let mut graph: Graph<String> = Graph::new(false);
+        graph.add_edge("auth".to_string(), "db".to_string()).unwrap();
+        graph.add_edge("db".to_string(), "cache".to_string()).unwrap();
+
+        let result = graph.bfs("auth".to_string()).unwrap();
+        assert_eq!(result, vec!["auth", "db", "cache"]);
+
+        let path = graph.shortest_path_bfs("cache".to_string(), "auth".to_string()).unwrap().unwrap();
+        assert_eq!(path, vec!["cache", "db", "auth"]);
+
+        let distances = graph.bfs_distances("auth".to_string()).unwrap();
+        assert_eq!(distances["cache"], 2);
+    }
+
+    #[test]
+    fn test_bfs_weighted_graph() {
+        let mut graph: WeightedGraph<i32, f64> = WeightedGraph::new(true);
+        graph.add_edge(1, 2, 0.5).unwrap();
+        graph.add_edge(1, 3, 2.0).unwrap();
+        graph.add_edge(3, 4, 1.5).unwrap();
+
+        assert_eq!(graph.bfs(1).unwrap(), vec![1, 2, 3, 4]);
+        assert_eq!(graph.shortest_path_bfs(1, 4).unwrap(), Some(vec![1, 3, 4]));
+        assert_eq!(graph.bfs_distances(1).unwrap()[&4], 2);
+        assert!(graph.shortest_path_bfs(4, 1).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_bfs_reverse() {
+        // 1 -> 2 -> 4, 3 -> 2
+        let mut graph = Graph::new(true);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(3, 2).unwrap();
+        graph.add_edge(2, 4).unwrap();
+
+        let mut upstream = graph.bfs_reverse(4).unwrap();
+        assert_eq!(upstream[..2], [4, 2]);
+        upstream[2..].sort();
+        assert_eq!(upstream[2..], [1, 3]);
+
+        graph.enable_reverse_index();
+        assert_eq!(graph.bfs_reverse(1).unwrap(), vec![1]);
+        assert_eq!(graph.bfs_reverse(2).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_bfs_tree_edges() {
+        let mut graph: WeightedGraph<i32, u32> = WeightedGraph::new(true);
+        let a = graph.add_edge(1, 2, 5).unwrap();
+        let b = graph.add_edge(1, 3, 7).unwrap();
+        graph.add_edge(2, 3, 1).unwrap();
+        let c = graph.add_edge(3, 4, 2).unwrap();
+
+        let tree = graph.bfs_tree_edges(1).unwrap();
+        let ids: Vec<_> = tree.iter().map(|e| e.id).collect();
+        assert_eq!(ids, vec![a, b, c]);
+        assert_eq!((tree[2].source, tree[2].target, tree[2].weight), (3, 4, 2));
+    }
+
+    #[test]
+    fn test_bfs_filtered_reads_payloads() {
+        #[derive(Clone)]
+        struct Service { owner: &'static str }
+        let mut graph: Graph<&str, Service, &str> = Graph::attributed(true, Default::default());
+        graph.add_node_with_data("gateway", Service { owner: "web" });
+        graph.add_node_with_data("auth", Service { owner: "identity" });
+        graph.add_node_with_data("billing", Service { owner: "payments" });
+        graph.add_node_with_data("ledger", Service { owner: "payments" });
+        graph.add_edge_with_data("gateway", "auth", "grpc").unwrap();
+        graph.add_edge_with_data("gateway", "billing", "http").unwrap();
+        graph.add_edge_with_data("auth", "ledger", "grpc").unwrap();
+        graph.add_edge_with_data("billing", "ledger", "grpc").unwrap();
+
+        let grpc = graph.bfs_filtered("gateway", |e| graph.edge_data(e.id) == Some(&"grpc")).unwrap();
+        assert_eq!(grpc, vec!["gateway", "auth", "ledger"]);
+
+        let payments = graph.bfs_filtered("gateway", |e| {
+            graph.node_data(&e.target).is_some_and(|s| s.owner == "payments")
+        }).unwrap();
+        assert_eq!(payments, vec!["gateway", "billing", "ledger"]);
+    }
+
+    struct Recorder {
+        events: Vec<String>,
+        prune: Option<i32>,
+        stop: Option<i32>,
+        max_depth: Option<usize>,
+    }
+
+    impl Recorder {
+        fn new() -> Self {
+            Recorder { events: Vec::new(), prune: None, stop: None, max_depth: None }
+        }
+    }
+
+    impl BfsVisitor<i32> for Recorder {
+        fn discover(&mut self, node: &i32, depth: usize) -> Control {
+            self.events.push(format!("discover {node}@{depth}"));
+            if self.stop == Some(*node) {
+                Control::Stop
+            } else if self.prune == Some(*node) {
+                Control::Prune
+            } else {
+                Control::Continue
+            }
+        }
+
+        fn examine_edge(&mut self, edge: &EdgeRef<i32>) -> Control {
+            self.events.push(format!("examine {}-{}", edge.source, edge.target));
+            Control::Continue
+        }
+
+        fn finish(&mut self, node: &i32) -> Control {
+            self.events.push(format!("finish {node}"));
+            Control::Continue
+        }
+
+        fn level(&mut self, depth: usize) -> Control {
+            self.events.push(format!("level {depth}"));
+            if self.max_depth == Some(depth) { Control::Prune } else { Control::Continue }
+        }
+    }
+
+    fn diamond() -> Graph {
+        let mut graph = Graph::new(true);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 3).unwrap();
+        graph.add_edge(2, 4).unwrap();
+        graph.add_edge(3, 4).unwrap();
+        graph.add_edge(4, 5).unwrap();
+        graph
+    }
+
+    #[test]
+    fn test_bfs_visit_event_order() {
+        let graph = diamond();
+        let mut recorder = Recorder::new();
+        graph.bfs_visit(1, &mut recorder).unwrap();
+        assert_eq!(recorder.events, [
+            "discover 1@0", "level 0", "examine 1-2", "discover 2@1", "examine 1-3", "discover 3@1", "finish 1",
+            "level 1", "examine 2-4", "discover 4@2", "finish 2", "examine 3-4", "finish 3",
+            "level 2", "examine 4-5", "discover 5@3", "finish 4", "level 3", "finish 5",
+        ]);
+        assert_eq!(graph.bfs_visit(9, &mut Recorder::new()), Err(GraphError::NodeNotFound));
+    }
+
+    #[test]
+    fn test_bfs_visit_prune_and_stop() {
+        let graph = diamond();
+        let mut pruned = Recorder::new();
+        pruned.prune = Some(2);
+        graph.bfs_visit(1, &mut pruned).unwrap();
+        // 2 is finished without being expanded, 4 is still reached through 3
+        assert!(pruned.events.contains(&"finish 2".to_string()));
+        assert!(!pruned.events.contains(&"examine 2-4".to_string()));
+        assert!(pruned.events.contains(&"discover 4@2".to_string()));
+
+        let mut bounded = Recorder::new();
+        bounded.max_depth = Some(1);
+        graph.bfs_visit(1, &mut bounded).unwrap();
+        assert_eq!(bounded.events.last().unwrap(), "finish 3");
+        assert!(!bounded.events.iter().any(|e| e.starts_with("discover 4")));
+
+        let mut stopped = Recorder::new();
+        stopped.stop = Some(3);
+        graph.bfs_visit(1, &mut stopped).unwrap();
+        assert_eq!(stopped.events.last().unwrap(), "discover 3@1");
+        assert!(!stopped.events.contains(&"finish 1".to_string()));
+    }
+
+    #[test]
+    fn test_bfs_iter_is_lazy_and_resumable() {
+        let mut graph = diamond();
+        graph.add_edge(6, 7).unwrap();
+        graph.add_node(8);
+
+        let mut bfs = graph.bfs_iter(1).unwrap();
+        assert_eq!(bfs.find(|&n| n > 2), Some(3));
+        assert!(bfs.is_visited(&4));
+        assert!(!bfs.is_visited(&5));
+        assert_eq!(graph.bfs_iter(1).unwrap().collect::<Vec<_>>(), graph.bfs(1).unwrap());
+        assert_eq!(graph.bfs_iter(1).unwrap().take_while(|&n| n != 4).count(), 3);
+
+        let mut forest = Bfs::new(&graph, 1).unwrap();
+        let mut components = vec![forest.by_ref().collect::<Vec<_>>()];
+        for node in [2, 6, 8, 7] {
+            forest.move_to(node).unwrap();
+            components.push(forest.by_ref().collect::<Vec<_>>());
+        }
+        assert_eq!(components, [vec![1, 2, 3, 4, 5], vec![], vec![6, 7], vec![8], vec![]]);
+        assert!(matches!(forest.move_to(9), Err(GraphError::NodeNotFound)));
+        let mut bfs = Bfs::new(&graph, 1).unwrap();
+        assert_eq!(bfs.next(), Some(1));
+        assert!(bfs.is_visited(&2));
+        bfs.move_to(3).unwrap();
+        assert!(!bfs.is_visited(&2));
+        assert_eq!(bfs.by_ref().collect::<Vec<_>>(), [3, 4, 5]);
+        bfs.move_to(2).unwrap();
+        assert_eq!(bfs.by_ref().collect::<Vec<_>>(), [2]);
+        bfs.move_to(1).unwrap();
+        assert_eq!(bfs.next(), None);
+    }
+
+    #[test]
+    fn test_bfs_forest_levels_and_roots() {
+        let mut graph = Graph::new(false);
+        graph.add_edge(8, 6).unwrap();
+        graph.add_edge(6, 7).unwrap();
+        graph.add_edge(3, 1).unwrap();
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(2, 3).unwrap();
+        graph.add_node(5);
+
+        let forest = graph.bfs_forest();
+        assert_eq!(forest.roots, [1, 5, 6]);
+        assert_eq!(forest.parent, HashMap::from([(2, 1), (3, 1), (7, 6), (8, 6)]));
+        assert_eq!(forest.level, HashMap::from([(1, 0), (2, 1), (3, 1), (5, 0), (6, 0), (7, 1), (8, 1)]));
+
+        let reversed = Reversed::new(&diamond()).bfs_forest();
+        assert_eq!(reversed.roots, [1, 2, 3, 4, 5]);
+        assert!(reversed.parent.is_empty());
+        assert_eq!(diamond().bfs_forest().level[&5], 3);
+    }
+}
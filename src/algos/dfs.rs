@@ -1,90 +1,443 @@
 use std::collections::{HashMap, HashSet};
-use crate::core::graph::Graph;
+use std::hash::Hash;
+
+use crate::algos::visited::VisitMap;
+use crate::algos::visitor::{Control, DfsVisitor};
+use crate::core::edge::{EdgeId, EdgeRef};
+use crate::core::error::GraphError;
+use crate::core::traits::{ensure_node, EdgeOf, GraphBase};
+use crate::core::views::Reversed;
+
+
+
+/// Depth-first traversals, available on every `GraphBase`.
+///
+/// Every traversal fails with `GraphError::NodeNotFound` if `start` is not
+/// in the graph.
+pub trait DfsExt: GraphBase {
+    /// Nodes reachable from `start` in preorder: each node, then what is
+    /// reached through its neighbors one by one, in `neighbors` order. Uses
+    /// an explicit stack, so arbitrarily deep graphs are fine.
+    fn dfs(&self, start: Self::Node) -> Result<Vec<Self::Node>, GraphError> {
+        Ok(Dfs::new(self, start)?.collect())
+    }
+
+    /// Same order as `dfs`, from a stack of single nodes instead of
+    /// per-node neighbor lists. Neighbors are pushed in reverse so the
+    /// first one is popped first.
+    fn dfs_iterative(&self, start: Self::Node) -> Result<Vec<Self::Node>, GraphError>{
+        ensure_node(self, &start)?;
+        let mut visited= VisitMap::new(self);
+        let mut stack = vec![start];
+        let mut result = Vec::new();
+
+        while let Some(node) = stack.pop(){
+            if visited.visit(&node) {
+                let neighbors: Vec<_> = self.neighbors(&node).filter(|n| !visited.is_visited(n)).collect();
+                stack.extend(neighbors.into_iter().rev());
+                result.push(node);
+            }
+        }
+        Ok(result)
+    }
 
+    /// `dfs` that only crosses edges for which `follow` returns `true`.
+    /// `follow` sees each edge before its target is visited, so it can
+    /// consult node or edge payloads by `edge.target` and `edge.id`.
+    fn dfs_filtered<F>(&self, start: Self::Node, follow: F) -> Result<Vec<Self::Node>, GraphError>
+    where
+        F: FnMut(&EdgeRef<Self::Node, Self::Weight>) -> bool,
+    {
+        let mut filtered = Filtered { follow, order: Vec::new() };
+        self.dfs_visit(start, &mut filtered)?;
+        Ok(filtered.order)
+    }
 
+    /// The edges through which `dfs` from `start` discovers each node, in
+    /// discovery order.
+    fn dfs_tree_edges(&self, start: Self::Node) -> Result<Vec<EdgeOf<Self>>, GraphError> {
+        let mut tree = TreeEdges(Vec::new());
+        self.dfs_visit(start, &mut tree)?;
+        Ok(tree.0)
+    }
 
-impl Graph{
-    pub fn dfs(&self, start: i32) ->Vec<i32>{
-    let mut visited = HashSet::new();
-    let mut result = Vec::new();
-    self.dfs_helper(start, &mut visited, &mut result);
-    result
+    /// DFS that follows edges backwards, i.e. from a node to its
+    /// predecessors.
+    fn dfs_reverse(&self, start: Self::Node) -> Result<Vec<Self::Node>, GraphError> {
+        Reversed::new(self).dfs(start)
     }
 
+    /// `Dfs` from `start`, yielding nodes on demand.
+    fn dfs_iter(&self, start: Self::Node) -> Result<Dfs<'_, Self>, GraphError> {
+        Dfs::new(self, start)
+    }
 
-    fn dfs_helper(&self, node: i32, visited: &mut HashSet<i32>,result:& mut Vec<i32>){
-        visited.insert(node);
-        result.push(node);
+    /// DFS from `start` reporting every event to `visitor`; see
+    /// `DfsVisitor` for the events and how `Prune` and `Stop` act on them.
+    /// Neighbors are explored in the same order as `dfs`, without
+    /// recursion.
+    fn dfs_visit<V>(&self, start: Self::Node, visitor: &mut V) -> Result<(), GraphError>
+    where
+        V: DfsVisitor<Self::Node, Self::Weight>,
+    {
+        ensure_node(self, &start)?;
+        visit_from_roots(self, [start], visitor);
+        Ok(())
+    }
 
-        if let Some(neighbors) = self.adj_list.get(&node){
-            for &neighbor in neighbors{
-                if !visited.contains(&neighbor){
-                    self.dfs_helper(neighbor, visited, result);
-                }
-            }
+    /// `dfs_visit` from every node not reached by an earlier root, so each
+    /// node is discovered exactly once. Roots are taken in `nodes()` order.
+    fn dfs_visit_all<V>(&self, visitor: &mut V)
+    where
+        V: DfsVisitor<Self::Node, Self::Weight>,
+    {
+        visit_from_roots(self, self.nodes(), visitor);
+    }
+
+    /// DFS over the whole graph: each node in ascending order that no
+    /// earlier search reached becomes the root of a new tree. Discovery and
+    /// finish times share one clock, so `u` is an ancestor of `v` exactly
+    /// when `u`'s interval contains `v`'s.
+    fn dfs_forest(&self) -> DfsForest<Self::Node>
+    where
+        Self::Node: Ord,
+    {
+        let mut roots: Vec<_> = self.nodes().collect();
+        roots.sort();
+        let mut builder = DfsForestBuilder {
+            forest: DfsForest { roots: Vec::new(), parent: HashMap::new(), discovery: HashMap::new(), finish: HashMap::new() },
+            clock: 0,
+        };
+        visit_from_roots(self, roots, &mut builder);
+        builder.forest
+    }
+
+    /// Nodes reachable from `start` in the order DFS finishes them, i.e.
+    /// each node after all nodes discovered from it.
+    fn dfs_postorder(&self, start: Self::Node) -> Result<Vec<Self::Node>, GraphError> {
+        let mut postorder = Postorder(Vec::new());
+        self.dfs_visit(start, &mut postorder)?;
+        Ok(postorder.0)
+    }
+
+    /// Whether any cycle exists: a directed cycle in a directed graph, or
+    /// in an undirected graph a cycle that does not reuse an edge.
+    /// Self-loops count.
+    fn has_cycle(&self) -> bool {
+        let mut finder = CycleFinder(false);
+        self.dfs_visit_all(&mut finder);
+        finder.0
+    }
+
+    /// Orders every node so each edge points from an earlier node to a
+    /// later one. Fails with `NotADag` if the graph has a cycle or is
+    /// undirected with at least one edge.
+    fn topological_sort(&self) -> Result<Vec<Self::Node>, GraphError> {
+        if !self.is_directed() && self.edges().next().is_some() {
+            return Err(GraphError::NotADag);
+        }
+        let mut sorter = TopoSort { order: Vec::new(), cyclic: false };
+        self.dfs_visit_all(&mut sorter);
+        if sorter.cyclic {
+            return Err(GraphError::NotADag);
         }
+        sorter.order.reverse();
+        Ok(sorter.order)
     }
+}
 
-    pub fn dfs_iterative(&self, start: i32) -> Vec<i32>{
-        let mut visited= HashSet::new();
-        let mut stack = vec![start];
-        let mut result = Vec::new();
+impl<G: GraphBase + ?Sized> DfsExt for G {}
+
+/// Spanning forest found by `DfsExt::dfs_forest`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DfsForest<N: Eq + Hash> {
+    /// The root of every tree, in the order the trees were grown.
+    pub roots: Vec<N>,
+    /// The node each non-root node was discovered from.
+    pub parent: HashMap<N, N>,
+    /// Clock value when each node was discovered.
+    pub discovery: HashMap<N, usize>,
+    /// Clock value when each node was finished.
+    pub finish: HashMap<N, usize>,
+}
+
+/// Lazy DFS yielding nodes in the same order as `dfs`.
+///
+/// Each stack frame holds the neighbors of a node still left to try, so
+/// nothing past the next node is explored. Like `Bfs`, the visited set is
+/// kept across `move_to`.
+pub struct Dfs<'a, G: GraphBase + ?Sized> {
+    graph: &'a G,
+    stack: Vec<std::vec::IntoIter<G::Node>>,
+    visited: VisitMap<'a, G>,
+}
 
+impl<'a, G: GraphBase + ?Sized> Dfs<'a, G> {
+    pub fn new(graph: &'a G, start: G::Node) -> Result<Self, GraphError> {
+        let mut dfs = Dfs { graph, stack: Vec::new(), visited: VisitMap::new(graph) };
+        dfs.move_to(start)?;
+        Ok(dfs)
+    }
 
-        while let Some(node) = stack.pop(){
-            if !visited.contains(&node) {
-                visited.insert(node);
-                result.push(node);
+    /// Drops the rest of the current traversal and continues from `start`,
+    /// which yields nothing if `start` was already visited.
+    pub fn move_to(&mut self, start: G::Node) -> Result<(), GraphError> {
+        ensure_node(self.graph, &start)?;
+        self.stack.clear();
+        self.stack.push(vec![start].into_iter());
+        Ok(())
+    }
 
-                if let Some(neighbors) = self.adj_list.get(&node){
+    /// Whether `node` has been yielded.
+    pub fn is_visited(&self, node: &G::Node) -> bool {
+        self.visited.is_visited(node)
+    }
+}
 
-                    for &neighbor in neighbors{
-                        if !visited.contains(&neighbor){
-                            stack.push(neighbor);
+impl<G: GraphBase + ?Sized> Iterator for Dfs<'_, G> {
+    type Item = G::Node;
+
+    fn next(&mut self) -> Option<G::Node> {
+        loop {
+            let frame = self.stack.last_mut()?;
+            let Some(node) = frame.next() else {
+                self.stack.pop();
+                continue;
+            };
+            if self.visited.visit(&node) {
+                let neighbors: Vec<_> = self.graph.neighbors(&node).collect();
+                self.stack.push(neighbors.into_iter());
+                return Some(node);
+            }
+        }
+    }
+}
+
+
+/// Iterative DFS shared by `dfs_visit` and `dfs_visit_all`. Each stack
+/// frame holds a node's remaining out-edges, so edges are handled in
+/// `out_edges` order, exactly as a recursive DFS would.
+fn visit_from_roots<G, V, I>(graph: &G, roots: I, visitor: &mut V)
+where
+    G: GraphBase + ?Sized,
+    V: DfsVisitor<G::Node, G::Weight>,
+    I: IntoIterator<Item = G::Node>,
+{
+    let directed = graph.is_directed();
+    // discovery index of every discovered node
+    let mut discovered: HashMap<G::Node, usize> = HashMap::new();
+    let mut finished: HashSet<G::Node> = HashSet::new();
+    // undirected edges already handled from their other end
+    let mut seen: HashSet<EdgeId> = HashSet::new();
+    let mut stack = Vec::new();
+
+    for root in roots {
+        if discovered.contains_key(&root) {
+            continue;
+        }
+        discovered.insert(root.clone(), discovered.len());
+        match visitor.discover(&root) {
+            Control::Stop => return,
+            Control::Prune => {
+                finished.insert(root.clone());
+                if visitor.finish(&root) == Control::Stop {
+                    return;
+                }
+                continue;
+            }
+            Control::Continue => stack.push((root.clone(), graph.out_edges(&root))),
+        }
+
+        while let Some((_, edges)) = stack.last_mut() {
+            let Some(edge) = edges.next() else {
+                let Some((node, _)) = stack.pop() else { break };
+                let stop = visitor.finish(&node) == Control::Stop;
+                finished.insert(node);
+                if stop {
+                    return;
+                }
+                continue;
+            };
+            if !directed && !seen.insert(edge.id) {
+                continue;
+            }
+            match visitor.examine_edge(&edge) {
+                Control::Stop => return,
+                Control::Prune => continue,
+                Control::Continue => {}
+            }
+
+            let control = match discovered.get(&edge.target) {
+                None => {
+                    match visitor.tree_edge(&edge) {
+                        Control::Stop => return,
+                        Control::Prune => {
+                            seen.remove(&edge.id);
+                            continue;
+                        }
+                        Control::Continue => {}
+                    }
+                    let target = edge.target;
+                    discovered.insert(target.clone(), discovered.len());
+                    match visitor.discover(&target) {
+                        Control::Stop => return,
+                        Control::Prune => {
+                            let control = visitor.finish(&target);
+                            finished.insert(target);
+                            control
+                        }
+                        Control::Continue => {
+                            let edges = graph.out_edges(&target);
+                            stack.push((target, edges));
+                            Control::Continue
                         }
                     }
                 }
+                Some(_) if !finished.contains(&edge.target) => visitor.back_edge(&edge),
+                Some(&to) if discovered[&edge.source] < to => visitor.forward_edge(&edge),
+                Some(_) => visitor.cross_edge(&edge),
+            };
+            if control == Control::Stop {
+                return;
             }
         }
-        result
+    }
+}
+
+/// Fills a `DfsForest`, ticking the clock on every discover and finish.
+struct DfsForestBuilder<N: Eq + Hash> {
+    forest: DfsForest<N>,
+    clock: usize,
+}
+
+impl<N: Eq + Hash + Clone, W> DfsVisitor<N, W> for DfsForestBuilder<N> {
+    fn discover(&mut self, node: &N) -> Control {
+        if !self.forest.parent.contains_key(node) {
+            self.forest.roots.push(node.clone());
+        }
+        self.forest.discovery.insert(node.clone(), self.clock);
+        self.clock += 1;
+        Control::Continue
+    }
+
+    fn tree_edge(&mut self, edge: &EdgeRef<N, W>) -> Control {
+        self.forest.parent.insert(edge.target.clone(), edge.source.clone());
+        Control::Continue
+    }
+
+    fn finish(&mut self, node: &N) -> Control {
+        self.forest.finish.insert(node.clone(), self.clock);
+        self.clock += 1;
+        Control::Continue
+    }
+}
+
+/// Preorder, crossing only edges `follow` accepts.
+struct Filtered<N, F> {
+    follow: F,
+    order: Vec<N>,
+}
+
+impl<N: Clone, W, F: FnMut(&EdgeRef<N, W>) -> bool> DfsVisitor<N, W> for Filtered<N, F> {
+    fn discover(&mut self, node: &N) -> Control {
+        self.order.push(node.clone());
+        Control::Continue
+    }
+
+    fn tree_edge(&mut self, edge: &EdgeRef<N, W>) -> Control {
+        if (self.follow)(edge) { Control::Continue } else { Control::Prune }
+    }
+}
+
+/// The edge that discovered each node.
+struct TreeEdges<N, W>(Vec<EdgeRef<N, W>>);
+
+impl<N: Clone, W: Clone> DfsVisitor<N, W> for TreeEdges<N, W> {
+    fn tree_edge(&mut self, edge: &EdgeRef<N, W>) -> Control {
+        self.0.push(edge.clone());
+        Control::Continue
+    }
+}
+
+/// Collects nodes as they finish.
+struct Postorder<N>(Vec<N>);
 
+impl<N: Clone, W> DfsVisitor<N, W> for Postorder<N> {
+    fn finish(&mut self, node: &N) -> Control {
+        self.0.push(node.clone());
+        Control::Continue
     }
 }
 
+/// Stops at the first back edge.
+struct CycleFinder(bool);
+
+impl<N, W> DfsVisitor<N, W> for CycleFinder {
+    fn back_edge(&mut self, _edge: &EdgeRef<N, W>) -> Control {
+        self.0 = true;
+        Control::Stop
+    }
+}
+
+/// Finish order, to be reversed, unless a back edge shows a cycle.
+struct TopoSort<N> {
+    order: Vec<N>,
+    cyclic: bool,
+}
+
+impl<N: Clone, W> DfsVisitor<N, W> for TopoSort<N> {
+    fn back_edge(&mut self, _edge: &EdgeRef<N, W>) -> Control {
+        self.cyclic = true;
+        Control::Stop
+    }
+
+    fn finish(&mut self, node: &N) -> Control {
+        self.order.push(node.clone());
+        Control::Continue
+    }
+}
 
 #[cfg(test)]
 mod tests{
     use super::*;
+    use std::collections::HashSet;
+    use crate::core::graph::Graph;
+    use crate::core::weightedgraph::WeightedGraph;
+    use std::collections::HashMap;
     #[test]
     fn test_dfs_single_node() {
         let mut graph = Graph::new(false);
-        graph.add_edge(1,1);
+        graph.add_edge(1,1).unwrap();
 
-        let result = graph.dfs(1);
+        let result = graph.dfs(1).unwrap();
         assert_eq!(result,vec![1]);
 
-        let result_iter = graph.dfs_iterative(1);
+        let result_iter = graph.dfs_iterative(1).unwrap();
         assert_eq!(result_iter,vec![1]);
 
     }
     #[test]
     fn test_dfs_isolated_node(){
         let mut graph = Graph::new(false);
-        let res = graph.dfs(5);
+        assert_eq!(graph.dfs(5), Err(GraphError::NodeNotFound));
+        assert_eq!(graph.dfs_iterative(5), Err(GraphError::NodeNotFound));
+
+        graph.add_node(5);
+        let res = graph.dfs(5).unwrap();
         assert_eq!(res,vec![5]);
 
-        let result_iter = graph.dfs_iterative(5);
+        let result_iter = graph.dfs_iterative(5).unwrap();
         assert_eq!(result_iter,vec![5] );
     }
 #[test]
 fn test_dfs_lin_path(){
     let mut graph = Graph::new(false);
-    graph.add_edge(1,2);
+    graph.add_edge(1,2).unwrap();
 
-    graph.add_edge(2,3);
-    graph.add_edge(3,4);
+    graph.add_edge(2,3).unwrap();
+    graph.add_edge(3,4).unwrap();
 
-    let result = graph.dfs(1);
+    let result = graph.dfs(1).unwrap();
     assert_eq!(result.len(), 4);
     assert!(result.contains(&1));
     assert!(result.contains(&2));
@@ -96,21 +449,21 @@ fn test_dfs_lin_path(){
     #[test]
     fn test_dfs_directed_vs_undirected() {
         let mut directed_graph = Graph::new(true);
-        directed_graph.add_edge(1, 2);
-        directed_graph.add_edge(2, 3);
+        directed_graph.add_edge(1, 2).unwrap();
+        directed_graph.add_edge(2, 3).unwrap();
 
-        let result_directed = directed_graph.dfs(1);
+        let result_directed = directed_graph.dfs(1).unwrap();
         assert_eq!(result_directed, vec![1, 2, 3]);
 
         // Starting from 3 in directed graph should only visit 3
-        let result_from_3 = directed_graph.dfs(3);
+        let result_from_3 = directed_graph.dfs(3).unwrap();
         assert_eq!(result_from_3, vec![3]);
 
         let mut undirected_graph = Graph::new(false);
-        undirected_graph.add_edge(1, 2);
-        undirected_graph.add_edge(2, 3);
+        undirected_graph.add_edge(1, 2).unwrap();
+        undirected_graph.add_edge(2, 3).unwrap();
 
-        let result_undirected = undirected_graph.dfs(3);
+        let result_undirected = undirected_graph.dfs(3).unwrap();
         assert_eq!(result_undirected.len(), 3);
         assert!(result_undirected.contains(&1));
         assert!(result_undirected.contains(&2));
@@ -121,11 +474,11 @@ fn test_dfs_lin_path(){
     fn test_dfs_cycle() {
         let mut graph = Graph::new(false);
         // Create a cycle: 1 - 2 - 3 - 1
-        graph.add_edge(1, 2);
-        graph.add_edge(2, 3);
-        graph.add_edge(3, 1);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(2, 3).unwrap();
+        graph.add_edge(3, 1).unwrap();
 
-        let result = graph.dfs(1);
+        let result = graph.dfs(1).unwrap();
         assert_eq!(result.len(), 3);
         assert!(result.contains(&1));
         assert!(result.contains(&2));
@@ -149,13 +502,13 @@ fn test_dfs_lin_path(){
         //     2   3
         //    /   / \
         //   4   5   6
-        graph.add_edge(1, 2);
-        graph.add_edge(1, 3);
-        graph.add_edge(2, 4);
-        graph.add_edge(3, 5);
-        graph.add_edge(3, 6);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 3).unwrap();
+        graph.add_edge(2, 4).unwrap();
+        graph.add_edge(3, 5).unwrap();
+        graph.add_edge(3, 6).unwrap();
 
-        let result = graph.dfs(1);
+        let result = graph.dfs(1).unwrap();
         assert_eq!(result.len(), 6);
         assert_eq!(result[0], 1); // Should start with root
 
@@ -170,11 +523,11 @@ fn test_dfs_lin_path(){
     fn test_dfs_disconnected_components() {
         let mut graph = Graph::new(false);
         // Two disconnected components: (1-2) and (3-4)
-        graph.add_edge(1, 2);
-        graph.add_edge(3, 4);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(3, 4).unwrap();
 
         // Starting from component 1
-        let result1 = graph.dfs(1);
+        let result1 = graph.dfs(1).unwrap();
         assert_eq!(result1.len(), 2);
         assert!(result1.contains(&1));
         assert!(result1.contains(&2));
@@ -182,7 +535,7 @@ fn test_dfs_lin_path(){
         assert!(!result1.contains(&4));
 
         // Starting from component 2
-        let result2 = graph.dfs(3);
+        let result2 = graph.dfs(3).unwrap();
         assert_eq!(result2.len(), 2);
         assert!(result2.contains(&3));
         assert!(result2.contains(&4));
@@ -198,11 +551,11 @@ fn test_dfs_lin_path(){
         // Complete graph with 4 nodes
         for i in 1..=4 {
             for j in (i+1)..=4 {
-                graph.add_edge(i, j);
+                graph.add_edge(i, j).unwrap();
             }
         }
 
-        let result = graph.dfs(1);
+        let result = graph.dfs(1).unwrap();
         assert_eq!(result.len(), 4);
         for i in 1..=4 {
             assert!(result.contains(&i));
@@ -214,17 +567,17 @@ fn test_dfs_lin_path(){
     fn test_dfs_star_graph() {
         let mut graph = Graph::new(false);
         // Star graph: center node 1 connected to 2, 3, 4, 5
-        graph.add_edge(1, 2);
-        graph.add_edge(1, 3);
-        graph.add_edge(1, 4);
-        graph.add_edge(1, 5);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 3).unwrap();
+        graph.add_edge(1, 4).unwrap();
+        graph.add_edge(1, 5).unwrap();
 
-        let result = graph.dfs(1);
+        let result = graph.dfs(1).unwrap();
         assert_eq!(result.len(), 5);
         assert_eq!(result[0], 1); // Center should be first
 
         // Starting from a leaf should visit all nodes
-        let result_from_leaf = graph.dfs(2);
+        let result_from_leaf = graph.dfs(2).unwrap();
         assert_eq!(result_from_leaf.len(), 5);
     }
 
@@ -233,10 +586,10 @@ fn test_dfs_lin_path(){
     #[test]
     fn test_dfs_negative_nodes() {
         let mut graph = Graph::new(false);
-        graph.add_edge(-1, -2);
-        graph.add_edge(-2, -3);
+        graph.add_edge(-1, -2).unwrap();
+        graph.add_edge(-2, -3).unwrap();
 
-        let result = graph.dfs(-1);
+        let result = graph.dfs(-1).unwrap();
         assert_eq!(result.len(), 3);
         assert!(result.contains(&-1));
         assert!(result.contains(&-2));
@@ -246,10 +599,10 @@ fn test_dfs_lin_path(){
     #[test]
     fn test_dfs_large_numbers() {
         let mut graph = Graph::new(false);
-        graph.add_edge(1_000_000, 2_000_000);
-        graph.add_edge(2_000_000, 3_000_000);
+        graph.add_edge(1_000_000, 2_000_000).unwrap();
+        graph.add_edge(2_000_000, 3_000_000).unwrap();
 
-        let result = graph.dfs(1_000_000);
+        let result = graph.dfs(1_000_000).unwrap();
         assert_eq!(result, vec![1_000_000, 2_000_000, 3_000_000]);
     }
 
@@ -257,23 +610,116 @@ fn test_dfs_lin_path(){
     fn test_dfs_recursive_vs_iterative() {
         let mut graph = Graph::new(false);
         // Create a more complex graph
-        graph.add_edge(1, 2);
-        graph.add_edge(1, 3);
-        graph.add_edge(2, 4);
-        graph.add_edge(3, 5);
-        graph.add_edge(4, 6);
-        graph.add_edge(5, 6);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 3).unwrap();
+        graph.add_edge(2, 4).unwrap();
+        graph.add_edge(3, 5).unwrap();
+        graph.add_edge(4, 6).unwrap();
+        graph.add_edge(5, 6).unwrap();
+
+        let result_recursive = graph.dfs(1).unwrap();
+        let result_iterative = graph.dfs_iterative(1).unwrap();
+
+        assert_eq!(result_recursive, [1, 2, 4, 6, 5, 3]);
+        assert_eq!(result_recursive, result_iterative);
+    }
 
-        let result_recursive = graph.dfs(1);
-        let result_iterative = graph.dfs_iterative(1);
+    /// The recursive definition the iterative traversals must agree with.
+    fn recursive_orders(graph: &Graph, node: i32, pre: &mut Vec<i32>, post: &mut Vec<i32>) {
+        pre.push(node);
+        for next in GraphBase::neighbors(graph, &node) {
+            if !pre.contains(&next) {
+                recursive_orders(graph, next, pre, post);
+            }
+        }
+        post.push(node);
+    }
 
-        // Both should visit the same number of nodes
-        assert_eq!(result_recursive.len(), result_iterative.len());
+    #[test]
+    fn test_dfs_orders_match_recursive_definition() {
+        for directed in [true, false] {
+            let mut graph = Graph::new(directed);
+            for (u, v) in [(1, 5), (1, 2), (5, 3), (2, 3), (3, 1), (2, 4), (4, 5), (6, 2), (4, 4)] {
+                graph.add_edge(u, v).unwrap();
+            }
+            for start in 1..=6 {
+                let (mut pre, mut post) = (Vec::new(), Vec::new());
+                recursive_orders(&graph, start, &mut pre, &mut post);
+                assert_eq!(graph.dfs(start).unwrap(), pre);
+                assert_eq!(graph.dfs_iterative(start).unwrap(), pre);
+                assert_eq!(graph.dfs_filtered(start, |_| true).unwrap(), pre);
+                assert_eq!(graph.dfs_postorder(start).unwrap(), post);
+            }
+        }
+    }
 
-        // Both should visit all the same nodes (order might differ)
-        let mut rec_set: HashSet<i32> = result_recursive.into_iter().collect();
-        let mut iter_set: HashSet<i32> = result_iterative.into_iter().collect();
-        assert_eq!(rec_set, iter_set);
+    #[test]
+    fn test_dfs_forest_covers_every_component() {
+        let mut graph = Graph::new(true);
+        graph.add_node(9);
+        graph.add_edge(7, 8).unwrap();
+        graph.add_edge(4, 2).unwrap();
+        graph.add_edge(2, 3).unwrap();
+        graph.add_edge(3, 2).unwrap();
+        graph.add_edge(1, 3).unwrap();
+
+        let forest = graph.dfs_forest();
+        assert_eq!(forest.roots, [1, 4, 7, 9]);
+        assert_eq!(forest.parent, HashMap::from([(3, 1), (2, 3), (8, 7)]));
+        assert_eq!(forest.discovery.len(), 7);
+        assert_eq!((forest.discovery[&1], forest.finish[&1]), (0, 5));
+        assert_eq!((forest.discovery[&2], forest.finish[&2]), (2, 3));
+        assert_eq!((forest.discovery[&4], forest.finish[&4]), (6, 7));
+        assert_eq!(forest.finish[&9], 13);
+        // descendants nest inside their ancestors' intervals
+        for (child, parent) in &forest.parent {
+            assert!(forest.discovery[parent] < forest.discovery[child]);
+            assert!(forest.finish[child] < forest.finish[parent]);
+        }
+        assert_eq!(Graph::<i32>::new(false).dfs_forest().roots, Vec::<i32>::new());
+    }
+
+    #[test]
+    fn test_dfs_deep_chain_does_not_overflow() {
+        let depth = 200_000;
+        let mut graph = Graph::new(true);
+        for i in 0..depth {
+            graph.add_edge(i, i + 1).unwrap();
+        }
+        let pre = graph.dfs(0).unwrap();
+        assert_eq!(pre.len(), depth as usize + 1);
+        assert_eq!(pre.last(), Some(&depth));
+        assert_eq!(graph.dfs_postorder(0).unwrap()[0], depth);
+        assert_eq!(graph.dfs_tree_edges(0).unwrap().len(), depth as usize);
+        assert_eq!(graph.dfs_filtered(0, |e| e.target < 10).unwrap().len(), 10);
+    }
+
+    #[test]
+    fn test_dfs_iter_matches_dfs_and_resumes() {
+        let mut graph = Graph::new(false);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 3).unwrap();
+        graph.add_edge(2, 4).unwrap();
+        graph.add_edge(3, 5).unwrap();
+        graph.add_edge(4, 6).unwrap();
+        graph.add_edge(5, 6).unwrap();
+        graph.add_edge(7, 8).unwrap();
+
+        assert_eq!(graph.dfs_iter(1).unwrap().collect::<Vec<_>>(), graph.dfs(1).unwrap());
+        let mut dfs = graph.dfs_iter(1).unwrap();
+        assert_eq!(dfs.find(|&n| n == 6), Some(6));
+        assert!(!dfs.is_visited(&3));
+        assert_eq!(dfs.next(), Some(5));
+
+        let mut forest = Dfs::new(&graph, 8).unwrap();
+        let mut seen = forest.by_ref().collect::<Vec<_>>();
+        for node in graph.nodes() {
+            forest.move_to(*node).unwrap();
+            seen.extend(forest.by_ref());
+        }
+        assert_eq!(seen.len(), 8);
+        assert_eq!(&seen[..2], [8, 7]);
+        assert!(matches!(graph.dfs_iter(0), Err(GraphError::NodeNotFound)));
     }
 
     #[test]
@@ -282,10 +728,10 @@ fn test_dfs_lin_path(){
 
         // Create a path of 1000 nodes
         for i in 0..999 {
-            graph.add_edge(i, i + 1);
+            graph.add_edge(i, i + 1).unwrap();
         }
 
-        let result = graph.dfs(0);
+        let result = graph.dfs(0).unwrap();
         assert_eq!(result.len(), 1000);
         assert_eq!(result[0], 0);
         assert_eq!(result[999], 999);
@@ -304,12 +750,12 @@ fn test_dfs_lin_path(){
     #[test]
     fn test_dfs_self_loops() {
         let mut graph = Graph::new(true); // Directed graph with self-loops
-        graph.add_edge(1, 1); // Self loop
-        graph.add_edge(1, 2);
-        graph.add_edge(2, 2); // Another self loop
-        graph.add_edge(2, 3);
+        graph.add_edge(1, 1).unwrap(); // Self loop
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(2, 2).unwrap(); // Another self loop
+        graph.add_edge(2, 3).unwrap();
 
-        let result = graph.dfs(1);
+        let result = graph.dfs(1).unwrap();
         assert_eq!(result.len(), 3);
         assert!(result.contains(&1));
         assert!(result.contains(&2));
@@ -320,9 +766,176 @@ fn test_dfs_lin_path(){
     fn test_dfs_empty_adjacency() {
         let mut graph = Graph::new(false);
         // Add a node that exists in adjacency list but has no neighbors
-        graph.adj_list.insert(42, Vec::new());
+        graph.add_node(42);
 
-        let result = graph.dfs(42);
+        let result = graph.dfs(42).unwrap();
         assert_eq!(result, vec![42]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_dfs_u64_nodes() {
+        let mut graph: Graph<u64> = Graph::new(true);
+        graph.add_edge(u64::MAX, 1).unwrap();
+        graph.add_edge(1, 2).unwrap();
+
+        assert_eq!(graph.dfs(u64::MAX).unwrap(), vec![u64::MAX, 1, 2]);
+        assert_eq!(graph.dfs_iterative(u64::MAX).unwrap(), vec![u64::MAX, 1, 2]);
+    }
+
+    #[test]
+    fn test_dfs_weighted_graph() {
+        let mut graph: WeightedGraph<&str, u64> = WeightedGraph::new(false);
+        graph.add_edge("a", "b", 10).unwrap();
+        graph.add_edge("b", "c", 20).unwrap();
+
+        assert_eq!(graph.dfs("a").unwrap(), vec!["a", "b", "c"]);
+        assert_eq!(graph.dfs_iterative("c").unwrap(), vec!["c", "b", "a"]);
+    }
+
+    #[test]
+    fn test_dfs_reverse() {
+        let mut graph: WeightedGraph<&str, u32> = WeightedGraph::new(true);
+        graph.enable_reverse_index();
+        graph.add_edge("app", "lib", 1).unwrap();
+        graph.add_edge("lib", "core", 1).unwrap();
+        graph.add_edge("tool", "core", 1).unwrap();
+
+        let dependents = graph.dfs_reverse("lib").unwrap();
+        assert_eq!(dependents, vec!["lib", "app"]);
+        assert_eq!(graph.dfs_reverse("core").unwrap().len(), 4);
+        assert_eq!(graph.dfs_reverse("app").unwrap(), vec!["app"]);
+    }
+
+    #[test]
+    fn test_dfs_tree_edges() {
+        let mut graph = Graph::new(false);
+        let a = graph.add_edge(1, 2).unwrap();
+        let b = graph.add_edge(2, 3).unwrap();
+        graph.add_edge(3, 1).unwrap();
+
+        let tree = graph.dfs_tree_edges(1).unwrap();
+        assert_eq!(tree.len(), 2);
+        assert_eq!((tree[0].id, tree[0].source, tree[0].target), (a, 1, 2));
+        assert_eq!((tree[1].id, tree[1].source, tree[1].target), (b, 2, 3));
+    }
+
+    #[test]
+    fn test_dfs_filtered_skips_by_edge_data() {
+        let mut graph: Graph<i32, (), u8> = Graph::attributed(false, Default::default());
+        graph.add_edge_with_data(1, 2, 1).unwrap();
+        graph.add_edge_with_data(2, 3, 9).unwrap();
+        graph.add_edge_with_data(1, 4, 2).unwrap();
+        graph.add_edge(4, 3).unwrap();
+
+        let light = graph.dfs_filtered(1, |e| graph.edge_data(e.id).is_some_and(|&cost| cost < 5)).unwrap();
+        let light: HashSet<_> = light.into_iter().collect();
+        assert_eq!(light, HashSet::from([1, 2, 4]));
+        assert_eq!(graph.dfs_filtered(3, |_| false).unwrap(), vec![3]);
+    }
+
+    /// Logs every event and prunes or stops at the given nodes.
+    #[derive(Default)]
+    struct Recorder {
+        events: Vec<String>,
+        prune: Option<i32>,
+        stop: Option<i32>,
+    }
+
+    impl DfsVisitor<i32> for Recorder {
+        fn discover(&mut self, node: &i32) -> Control {
+            self.events.push(format!("discover {node}"));
+            if self.stop == Some(*node) {
+                Control::Stop
+            } else if self.prune == Some(*node) {
+                Control::Prune
+            } else {
+                Control::Continue
+            }
+        }
+
+        fn tree_edge(&mut self, edge: &EdgeRef<i32>) -> Control {
+            self.events.push(format!("tree {}-{}", edge.source, edge.target));
+            Control::Continue
+        }
+
+        fn back_edge(&mut self, edge: &EdgeRef<i32>) -> Control {
+            self.events.push(format!("back {}-{}", edge.source, edge.target));
+            Control::Continue
+        }
+
+        fn forward_edge(&mut self, edge: &EdgeRef<i32>) -> Control {
+            self.events.push(format!("forward {}-{}", edge.source, edge.target));
+            Control::Continue
+        }
+
+        fn cross_edge(&mut self, edge: &EdgeRef<i32>) -> Control {
+            self.events.push(format!("cross {}-{}", edge.source, edge.target));
+            Control::Continue
+        }
+
+        fn finish(&mut self, node: &i32) -> Control {
+            self.events.push(format!("finish {node}"));
+            Control::Continue
+        }
+    }
+
+    #[test]
+    fn test_dfs_visit_classifies_directed_edges() {
+        let graph = Graph::from_edges(true, [(1, 2), (2, 3), (3, 1), (1, 3), (1, 4), (4, 3)]);
+        let mut recorder = Recorder::default();
+        graph.dfs_visit(1, &mut recorder).unwrap();
+        assert_eq!(recorder.events, [
+            "discover 1", "tree 1-2", "discover 2", "tree 2-3", "discover 3", "back 3-1", "finish 3", "finish 2",
+            "forward 1-3", "tree 1-4", "discover 4", "cross 4-3", "finish 4", "finish 1",
+        ]);
+        assert_eq!(graph.dfs_visit(9, &mut recorder), Err(GraphError::NodeNotFound));
+    }
+
+    #[test]
+    fn test_dfs_visit_undirected_edges_once() {
+        let graph = Graph::from_edges(false, [(1, 2), (2, 3), (3, 1)]);
+        let mut recorder = Recorder::default();
+        graph.dfs_visit(1, &mut recorder).unwrap();
+        let edges: Vec<_> = recorder.events.iter().filter(|e| !e.starts_with("discover") && !e.starts_with("finish")).collect();
+        assert_eq!(edges, ["tree 1-2", "tree 2-3", "back 3-1"]);
+
+        assert!(graph.has_cycle());
+        assert!(!Graph::from_edges(false, [(1, 2), (2, 3)]).has_cycle());
+        assert!(Graph::from_edges(false, [(1, 1)]).has_cycle());
+        assert!(Graph::from_edges(false, [(1, 2), (1, 2)]).has_cycle());
+    }
+
+    #[test]
+    fn test_dfs_visit_prune_and_stop() {
+        let graph = Graph::from_edges(true, [(1, 2), (2, 3), (1, 4), (4, 5)]);
+        let mut pruned = Recorder { prune: Some(2), ..Recorder::default() };
+        graph.dfs_visit(1, &mut pruned).unwrap();
+        assert!(!pruned.events.contains(&"discover 3".to_string()));
+        assert!(pruned.events.contains(&"finish 2".to_string()));
+        assert!(pruned.events.contains(&"discover 5".to_string()));
+
+        let mut stopped = Recorder { stop: Some(4), ..Recorder::default() };
+        graph.dfs_visit(1, &mut stopped).unwrap();
+        assert_eq!(stopped.events.last().map(String::as_str), Some("discover 4"));
+        assert!(!stopped.events.contains(&"finish 1".to_string()));
+    }
+
+    #[test]
+    fn test_postorder_and_topological_sort() {
+        let graph = Graph::from_edges(true, [("shirt", "tie"), ("tie", "jacket"), ("trousers", "shoes"), ("trousers", "belt"), ("belt", "jacket"), ("socks", "shoes")]);
+        assert_eq!(graph.dfs_postorder("trousers").unwrap(), ["shoes", "jacket", "belt", "trousers"]);
+
+        let order = graph.topological_sort().unwrap();
+        assert_eq!(order.len(), graph.node_count());
+        let position: HashMap<_, _> = order.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+        assert!(graph.edges().all(|e| position[e.source] < position[e.target]));
+        assert!(!graph.has_cycle());
+
+        let mut cyclic = graph.clone();
+        cyclic.add_edge("jacket", "shirt").unwrap();
+        assert!(cyclic.has_cycle());
+        assert_eq!(cyclic.topological_sort(), Err(GraphError::NotADag));
+        assert_eq!(Graph::from_edges(false, [(1, 2)]).topological_sort(), Err(GraphError::NotADag));
+        assert_eq!(Graph::<i32>::new(true).topological_sort(), Ok(Vec::new()));
+    }
+}
@@ -1,2 +1,5 @@
 pub mod dfs;
-pub mod bfs;
\ No newline at end of file
+pub mod bfs;
+pub mod temporal;
+pub mod visitor;
+mod visited;
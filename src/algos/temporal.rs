@@ -0,0 +1,79 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::core::error::GraphError;
+use crate::core::traits::{ensure_node, EdgeOf, GraphBase};
+use crate::core::weight::Weight;
+
+/// Time-respecting traversals for graphs whose edge weight is the time the
+/// edge can be crossed, such as `TemporalGraph` and its views.
+///
+/// A journey may only take an edge at or after the time it reached the
+/// edge's source, so timestamps along it never decrease.
+pub trait TemporalExt: GraphBase
+where
+    Self::Weight: Weight,
+{
+    /// Earliest time each node can be reached from `start` when leaving at
+    /// `depart`. Nodes no journey reaches are missing; `start` maps to
+    /// `depart`.
+    fn earliest_arrival(&self, start: Self::Node, depart: Self::Weight) -> Result<HashMap<Self::Node, Self::Weight>, GraphError> {
+        Ok(earliest_arrival_tree(self, start, depart)?.0)
+    }
+
+    /// The edges of a journey from `start` to `end` that arrives as early
+    /// as possible, or `Ok(None)` if no journey leaving at `depart` gets
+    /// there. The path from `start` to itself is empty.
+    fn earliest_arrival_path(&self, start: Self::Node, end: Self::Node, depart: Self::Weight) -> Result<Option<Vec<EdgeOf<Self>>>, GraphError> {
+        ensure_node(self, &end)?;
+        let (arrival, parent) = earliest_arrival_tree(self, start.clone(), depart)?;
+        if !arrival.contains_key(&end) {
+            return Ok(None);
+        }
+        let mut path = Vec::new();
+        let mut node = end;
+        while node != start {
+            let Some(edge) = parent.get(&node) else { break };
+            node = edge.source.clone();
+            path.push(edge.clone());
+        }
+        path.reverse();
+        Ok(Some(path))
+    }
+}
+
+impl<G: GraphBase + ?Sized> TemporalExt for G where G::Weight: Weight {}
+
+/// Arrival times and the edge each node was first reached by at that time.
+type ArrivalTree<G> = (HashMap<<G as GraphBase>::Node, <G as GraphBase>::Weight>, HashMap<<G as GraphBase>::Node, EdgeOf<G>>);
+
+/// Label-correcting BFS: a node is queued again whenever a journey reaches
+/// it earlier than before, until no arrival time improves.
+fn earliest_arrival_tree<G>(graph: &G, start: G::Node, depart: G::Weight) -> Result<ArrivalTree<G>, GraphError>
+where
+    G: GraphBase + ?Sized,
+    G::Weight: Weight,
+{
+    ensure_node(graph, &start)?;
+    if !depart.is_valid() {
+        return Err(GraphError::InvalidWeight);
+    }
+    let mut arrival = HashMap::new();
+    let mut parent: HashMap<G::Node, EdgeOf<G>> = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    arrival.insert(start.clone(), depart);
+    queue.push_back(start);
+
+    while let Some(node) = queue.pop_front() {
+        let reached = arrival[&node];
+        for edge in graph.out_edges(&node) {
+            if edge.weight < reached || arrival.get(&edge.target).is_some_and(|&t| t <= edge.weight) {
+                continue;
+            }
+            arrival.insert(edge.target.clone(), edge.weight);
+            queue.push_back(edge.target.clone());
+            parent.insert(edge.target.clone(), edge);
+        }
+    }
+    Ok((arrival, parent))
+}
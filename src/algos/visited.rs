@@ -0,0 +1,55 @@
+use std::collections::HashSet;
+
+use crate::core::traits::GraphBase;
+
+/// Visited set used by the traversals.
+///
+/// Backends that report dense node indices get a flat `Vec<bool>`; everything
+/// else falls back to hashing the nodes.
+pub(crate) struct VisitMap<'a, G: GraphBase + ?Sized> {
+    graph: &'a G,
+    dense: Vec<bool>,
+    sparse: HashSet<G::Node>,
+}
+
+impl<'a, G: GraphBase + ?Sized> VisitMap<'a, G> {
+    pub(crate) fn new(graph: &'a G) -> Self {
+        VisitMap {
+            graph,
+            dense: vec![false; graph.node_bound().unwrap_or(0)],
+            sparse: HashSet::new(),
+        }
+    }
+
+    /// Marks `node` as visited, returning `true` if it was not visited before.
+    pub(crate) fn visit(&mut self, node: &G::Node) -> bool {
+        match self.slot(node) {
+            Some(i) => !std::mem::replace(&mut self.dense[i], true),
+            None => self.sparse.insert(node.clone()),
+        }
+    }
+
+    /// Clears the mark of `node`, so a later `visit` counts it as new.
+    pub(crate) fn unvisit(&mut self, node: &G::Node) {
+        match self.slot(node) {
+            Some(i) => self.dense[i] = false,
+            None => {
+                self.sparse.remove(node);
+            }
+        }
+    }
+
+    pub(crate) fn is_visited(&self, node: &G::Node) -> bool {
+        match self.slot(node) {
+            Some(i) => self.dense[i],
+            None => self.sparse.contains(node),
+        }
+    }
+
+    fn slot(&self, node: &G::Node) -> Option<usize> {
+        if self.dense.is_empty() {
+            return None;
+        }
+        self.graph.node_index(node).filter(|&i| i < self.dense.len())
+    }
+}
@@ -0,0 +1,95 @@
+use crate::core::edge::EdgeRef;
+
+/// What a visitor callback asks the traversal to do next.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Control {
+    Continue,
+    /// Skip what the event would lead to; see each callback for what that
+    /// is.
+    Prune,
+    /// End the whole traversal now.
+    Stop,
+}
+
+/// Callbacks for `DfsExt::dfs_visit`, all defaulting to `Continue`.
+///
+/// In a directed graph every edge out of a discovered node is examined and
+/// then classified as exactly one of tree, back, forward or cross edge. In
+/// an undirected graph each edge is examined once, from the end reached
+/// first, and is a tree or a back edge.
+pub trait DfsVisitor<N, W = ()> {
+    /// `node` is reached for the first time. `Prune` skips its out-edges;
+    /// it is finished right away.
+    fn discover(&mut self, _node: &N) -> Control {
+        Control::Continue
+    }
+
+    /// An out-edge of the node being explored. `Prune` skips it without
+    /// classifying it.
+    fn examine_edge(&mut self, _edge: &EdgeRef<N, W>) -> Control {
+        Control::Continue
+    }
+
+    /// The edge discovers its target. `Prune` leaves the target
+    /// undiscovered, so another edge may still reach it.
+    fn tree_edge(&mut self, _edge: &EdgeRef<N, W>) -> Control {
+        Control::Continue
+    }
+
+    /// The target is on the current DFS path, so the edge closes a cycle.
+    fn back_edge(&mut self, _edge: &EdgeRef<N, W>) -> Control {
+        Control::Continue
+    }
+
+    /// The target is an already finished descendant of the source.
+    fn forward_edge(&mut self, _edge: &EdgeRef<N, W>) -> Control {
+        Control::Continue
+    }
+
+    /// The target was finished in another subtree or an earlier tree.
+    fn cross_edge(&mut self, _edge: &EdgeRef<N, W>) -> Control {
+        Control::Continue
+    }
+
+    /// Every out-edge of `node` has been handled. `Prune` means the same as
+    /// `Continue`.
+    fn finish(&mut self, _node: &N) -> Control {
+        Control::Continue
+    }
+}
+
+/// Callbacks for `BfsExt::bfs_visit`, all defaulting to `Continue`.
+///
+/// Nodes are discovered when first reached and finished when taken off the
+/// queue, after their out-edges, so both happen in the same order.
+pub trait BfsVisitor<N, W = ()> {
+    /// `node` is reached for the first time, `depth` hops from the start.
+    /// `Prune` keeps it from being expanded; it is still finished in turn.
+    fn discover(&mut self, _node: &N, _depth: usize) -> Control {
+        Control::Continue
+    }
+
+    /// An out-edge of the node being expanded, whatever its target. `Prune`
+    /// skips it.
+    fn examine_edge(&mut self, _edge: &EdgeRef<N, W>) -> Control {
+        Control::Continue
+    }
+
+    /// An examined edge whose target has not been discovered yet. `Prune`
+    /// leaves the target undiscovered, so another edge may still reach it.
+    fn tree_edge(&mut self, _edge: &EdgeRef<N, W>) -> Control {
+        Control::Continue
+    }
+
+    /// Every out-edge of `node` has been handled. `Prune` means the same as
+    /// `Continue`.
+    fn finish(&mut self, _node: &N) -> Control {
+        Control::Continue
+    }
+
+    /// The first node at `depth` is about to be expanded. `Prune` stops
+    /// expanding nodes from this depth on, which bounds the search depth.
+    fn level(&mut self, _depth: usize) -> Control {
+        Control::Continue
+    }
+}
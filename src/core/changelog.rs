@@ -0,0 +1,225 @@
+use std::fmt;
+
+use crate::core::edge::{EdgeId, EdgeRef};
+
+/// One structural mutation of a graph. `W` is the edge weight, `()` for
+/// `Graph`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change<N, W = ()> {
+    NodeAdded(N),
+    /// Emitted after the `EdgeRemoved` of every edge the node had.
+    NodeRemoved(N),
+    /// Emitted after the `NodeAdded` of endpoints the edge created.
+    EdgeAdded(EdgeRef<N, W>),
+    EdgeRemoved(EdgeRef<N, W>),
+    WeightChanged { id: EdgeId, old: W, new: W },
+}
+
+/// A `Change` stamped with the graph version it produced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delta<N, W = ()> {
+    pub version: u64,
+    pub change: Change<N, W>,
+}
+
+/// Handle returned by `ChangeLog::subscribe`, used to unsubscribe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ObserverId(usize);
+
+type Observer<N, W> = Box<dyn FnMut(&Delta<N, W>) + Send + Sync>;
+
+/// Version counter, optional delta buffer and observers of one graph.
+///
+/// The version goes up by one for every change, whether or not anyone is
+/// listening. Deltas are only buffered while recording is on, and stay
+/// buffered until drained, so a consumer that never drains keeps every
+/// change in memory. Observers are called synchronously as each change
+/// happens.
+pub struct ChangeLog<N, W = ()> {
+    version: u64,
+    recording: bool,
+    /// Version just before `deltas[0]`; the buffer holds every delta after
+    /// it.
+    base: u64,
+    deltas: Vec<Delta<N, W>>,
+    observers: Vec<(ObserverId, Observer<N, W>)>,
+    next_observer: usize,
+}
+
+impl<N, W> ChangeLog<N, W> {
+    pub fn new() -> Self {
+        ChangeLog {
+            version: 0,
+            recording: false,
+            base: 0,
+            deltas: Vec::new(),
+            observers: Vec::new(),
+            next_observer: 0,
+        }
+    }
+
+    /// Version after the latest change, `0` for a graph never mutated.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Starts buffering deltas from the current version on.
+    pub fn start_recording(&mut self) {
+        if !self.recording {
+            self.recording = true;
+            self.base = self.version;
+        }
+    }
+
+    /// Stops buffering and drops the deltas not drained yet.
+    pub fn stop_recording(&mut self) {
+        self.recording = false;
+        self.deltas.clear();
+    }
+
+    /// The buffered deltas newer than `version`, oldest first. `None` if
+    /// some of them are not buffered, because recording was off or they
+    /// were drained, in which case the caller has to rebuild from scratch.
+    pub fn since(&self, version: u64) -> Option<&[Delta<N, W>]> {
+        if !self.recording || version < self.base {
+            return None;
+        }
+        let skip = usize::try_from(version - self.base).ok()?;
+        Some(self.deltas.get(skip..).unwrap_or(&[]))
+    }
+
+    /// Like `since`, but takes the deltas out and discards the older ones
+    /// too. Nothing is drained when `None` is returned.
+    pub fn drain_since(&mut self, version: u64) -> Option<Vec<Delta<N, W>>> {
+        self.since(version)?;
+        let skip = (version - self.base) as usize;
+        let mut deltas = std::mem::take(&mut self.deltas);
+        self.base = self.version;
+        Some(deltas.drain(skip.min(deltas.len())..).collect())
+    }
+
+    /// Registers `observer` to be called with every later change.
+    pub fn subscribe<F>(&mut self, observer: F) -> ObserverId
+    where
+        F: FnMut(&Delta<N, W>) + Send + Sync + 'static,
+    {
+        let id = ObserverId(self.next_observer);
+        self.next_observer += 1;
+        self.observers.push((id, Box::new(observer)));
+        id
+    }
+
+    /// Removes an observer. Returns `false` if it was not registered.
+    pub fn unsubscribe(&mut self, id: ObserverId) -> bool {
+        let before = self.observers.len();
+        self.observers.retain(|(x, _)| *x != id);
+        self.observers.len() != before
+    }
+
+    /// Whether a change would be buffered or seen by an observer. Lets
+    /// callers skip collecting what `record` would throw away.
+    pub(crate) fn is_observed(&self) -> bool {
+        self.recording || !self.observers.is_empty()
+    }
+
+    /// Bumps the version and, if anyone is listening, builds the change and
+    /// hands it out.
+    pub(crate) fn record<F: FnOnce() -> Change<N, W>>(&mut self, change: F) {
+        self.version += 1;
+        if !self.is_observed() {
+            return;
+        }
+        let delta = Delta { version: self.version, change: change() };
+        for (_, observer) in &mut self.observers {
+            observer(&delta);
+        }
+        if self.recording {
+            self.deltas.push(delta);
+        }
+    }
+
+    /// Bumps the version for `count` changes nobody is listening to.
+    pub(crate) fn skip(&mut self, count: usize) {
+        debug_assert!(!self.is_observed());
+        self.version += count as u64;
+    }
+}
+
+impl<N, W> Default for ChangeLog<N, W> {
+    fn default() -> Self {
+        ChangeLog::new()
+    }
+}
+
+/// Copies the version and buffered deltas. Observers are closures tied to
+/// the original graph and are not carried over.
+impl<N: Clone, W: Clone> Clone for ChangeLog<N, W> {
+    fn clone(&self) -> Self {
+        ChangeLog {
+            version: self.version,
+            recording: self.recording,
+            base: self.base,
+            deltas: self.deltas.clone(),
+            observers: Vec::new(),
+            next_observer: self.next_observer,
+        }
+    }
+}
+
+impl<N: fmt::Debug, W: fmt::Debug> fmt::Debug for ChangeLog<N, W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChangeLog")
+            .field("version", &self.version)
+            .field("recording", &self.recording)
+            .field("deltas", &self.deltas)
+            .field("observers", &self.observers.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_since_and_drain() {
+        let mut log: ChangeLog<i32> = ChangeLog::new();
+        log.record(|| Change::NodeAdded(0));
+        assert_eq!(log.since(0), None);
+
+        log.start_recording();
+        log.record(|| Change::NodeAdded(1));
+        log.record(|| Change::NodeAdded(2));
+        assert_eq!(log.version(), 3);
+        assert_eq!(log.since(0), None);
+        assert_eq!(log.since(2).unwrap(), [Delta { version: 3, change: Change::NodeAdded(2) }]);
+        assert_eq!(log.since(3).unwrap(), []);
+
+        let drained = log.drain_since(1).unwrap();
+        assert_eq!(drained.len(), 2);
+        assert_eq!(log.since(2), None);
+        log.record(|| Change::NodeRemoved(1));
+        assert_eq!(log.drain_since(3).unwrap(), [Delta { version: 4, change: Change::NodeRemoved(1) }]);
+    }
+
+    #[test]
+    fn test_observers() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let mut log: ChangeLog<&str> = ChangeLog::new();
+        let sink = Arc::clone(&seen);
+        let id = log.subscribe(move |d| sink.lock().unwrap().push(d.version));
+
+        log.record(|| Change::NodeAdded("a"));
+        assert!(log.clone().since(0).is_none());
+        assert!(log.unsubscribe(id));
+        assert!(!log.unsubscribe(id));
+        log.record(|| Change::NodeAdded("b"));
+        assert_eq!(*seen.lock().unwrap(), [1]);
+        assert!(log.since(0).is_none());
+    }
+}
@@ -0,0 +1,364 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::core::edge::{EdgeId, EdgeRef};
+use crate::core::graph::Graph;
+use crate::core::traits::GraphBase;
+use crate::core::weight::Weight;
+use crate::core::weightedgraph::WeightedGraph;
+
+/// Frozen compressed sparse row graph.
+///
+/// Nodes are renumbered to dense indices `0..node_count()`. The out-neighbors
+/// of node `i` are `targets[offsets[i]..offsets[i + 1]]`, with the matching
+/// edge weights and ids at the same positions in `weights` and `ids`. Graphs
+/// built from an unweighted `Graph` use `W = ()`, so the weights array takes
+/// no memory. Edge ids are copied from the source graph.
+///
+/// Neighbor order is copied from the source graph, so traversals visit nodes
+/// in the same order as on the graph the CSR was built from.
+///
+/// `EData` is the edge payload type of CSRs built with `with_data`; the
+/// payloads are kept apart from the weights and looked up by edge id.
+#[derive(Debug, Clone)]
+pub struct CsrGraph<N = i32, W = (), EData = ()> {
+    nodes: Vec<N>,
+    index: HashMap<N, usize>,
+    offsets: Vec<usize>,
+    targets: Vec<usize>,
+    weights: Vec<W>,
+    ids: Vec<EdgeId>,
+    /// Edge payloads aligned with `ids`, empty unless built by `with_data`.
+    edge_data: Vec<Option<EData>>,
+    /// Position in `ids` of each edge, filled alongside `edge_data`.
+    edge_pos: HashMap<EdgeId, usize>,
+    is_directed: bool,
+}
+
+impl<N: Eq + Hash + Clone, W: Clone, EData> CsrGraph<N, W, EData> {
+    /// Builds the CSR arrays from any backend that can list, per node, its
+    /// out-neighbors together with the edge weight and id.
+    fn build<I, E>(is_directed: bool, adjacency: I) -> Self
+    where
+        I: IntoIterator<Item = (N, E)>,
+        E: IntoIterator<Item = (N, W, EdgeId)>,
+    {
+        let mut graph = CsrGraph {
+            nodes: Vec::new(),
+            index: HashMap::new(),
+            offsets: Vec::new(),
+            targets: Vec::new(),
+            weights: Vec::new(),
+            ids: Vec::new(),
+            edge_data: Vec::new(),
+            edge_pos: HashMap::new(),
+            is_directed,
+        };
+
+        let mut rows: Vec<Vec<(usize, W, EdgeId)>> = Vec::new();
+        for (u, out) in adjacency {
+            let ui = graph.intern(u, &mut rows);
+            for (v, w, id) in out {
+                let vi = graph.intern(v, &mut rows);
+                rows[ui].push((vi, w, id));
+            }
+        }
+
+        graph.offsets.reserve(rows.len() + 1);
+        graph.offsets.push(0);
+        for row in rows {
+            for (v, w, id) in row {
+                graph.targets.push(v);
+                graph.weights.push(w);
+                graph.ids.push(id);
+            }
+            graph.offsets.push(graph.targets.len());
+        }
+        graph
+    }
+
+    fn intern(&mut self, node: N, rows: &mut Vec<Vec<(usize, W, EdgeId)>>) -> usize {
+        if let Some(&i) = self.index.get(&node) {
+            return i;
+        }
+        let i = self.nodes.len();
+        self.index.insert(node.clone(), i);
+        self.nodes.push(node);
+        rows.push(Vec::new());
+        i
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Number of edges, counting each undirected edge once.
+    pub fn edge_count(&self) -> usize {
+        if self.is_directed {
+            self.targets.len()
+        } else {
+            self.targets.len() / 2
+        }
+    }
+
+    /// Dense index assigned to `node`, if it is part of the graph.
+    pub fn index_of(&self, node: &N) -> Option<usize> {
+        self.index.get(node).copied()
+    }
+
+    /// The node stored at dense index `i`.
+    ///
+    /// Panics if `i >= node_count()`.
+    pub fn node(&self, i: usize) -> &N {
+        &self.nodes[i]
+    }
+
+    /// Dense indices of the out-neighbors of the node at index `i`.
+    pub fn neighbor_indices(&self, i: usize) -> &[usize] {
+        &self.targets[self.offsets[i]..self.offsets[i + 1]]
+    }
+
+    /// Weights of the out-edges of the node at index `i`, aligned with
+    /// `neighbor_indices(i)`.
+    pub fn neighbor_weights(&self, i: usize) -> &[W] {
+        &self.weights[self.offsets[i]..self.offsets[i + 1]]
+    }
+
+    /// Ids of the out-edges of the node at index `i`, aligned with
+    /// `neighbor_indices(i)`.
+    pub fn neighbor_edge_ids(&self, i: usize) -> &[EdgeId] {
+        &self.ids[self.offsets[i]..self.offsets[i + 1]]
+    }
+
+    /// Payload of the edge with the given id, if the CSR was built with
+    /// `with_data` and the edge had one.
+    pub fn edge_data(&self, id: EdgeId) -> Option<&EData> {
+        self.edge_data.get(*self.edge_pos.get(&id)?)?.as_ref()
+    }
+
+    /// Out-edges of the node at index `i`, with both endpoints as indices.
+    fn row_edges(&self, i: usize) -> impl Iterator<Item = EdgeRef<usize, W>> + '_ {
+        (self.offsets[i]..self.offsets[i + 1]).map(move |k| EdgeRef {
+            source: i,
+            target: self.targets[k],
+            weight: self.weights[k].clone(),
+            id: self.ids[k],
+        })
+    }
+
+    /// View of this graph whose nodes are the dense indices themselves.
+    ///
+    /// Traversals over the view never hash: neighbors come straight from the
+    /// `targets` array and visited sets are plain vectors. Map results back
+    /// with `node`.
+    pub fn indices(&self) -> CsrIndices<'_, N, W, EData> {
+        CsrIndices { csr: self }
+    }
+}
+
+impl<N: Eq + Hash + Clone, EData: Clone> CsrGraph<N, (), EData> {
+    /// Builds a CSR from `graph` with its payloads. Edge payloads are read
+    /// back with `edge_data`, and the returned vector holds the payload of
+    /// the node at each dense index.
+    pub fn with_data<NData: Clone>(graph: &Graph<N, NData, EData>) -> (Self, Vec<Option<NData>>) {
+        let mut csr: Self = CsrGraph::build(
+            graph.is_directed(),
+            graph.nodes().map(|u| {
+                (u.clone(), graph.out_edges(u).map(|e| (e.target.clone(), (), e.id)))
+            }),
+        );
+        csr.edge_data = csr.ids.iter().map(|&id| graph.edge_data(id).cloned()).collect();
+        for (k, &id) in csr.ids.iter().enumerate() {
+            csr.edge_pos.entry(id).or_insert(k);
+        }
+        let node_data = csr.nodes.iter().map(|n| graph.node_data(n).cloned()).collect();
+        (csr, node_data)
+    }
+}
+
+/// Structure only: node and edge payloads are not carried over. Use
+/// `CsrGraph::with_data` to keep them.
+impl<N: Eq + Hash + Clone, NData, EData> From<&Graph<N, NData, EData>> for CsrGraph<N, ()> {
+    fn from(graph: &Graph<N, NData, EData>) -> Self {
+        CsrGraph::build(
+            graph.is_directed(),
+            graph.nodes().map(|u| {
+                (u.clone(), graph.out_edges(u).map(|e| (e.target.clone(), (), e.id)))
+            }),
+        )
+    }
+}
+
+impl<N: Eq + Hash + Clone, W: Weight> From<&WeightedGraph<N, W>> for CsrGraph<N, W> {
+    fn from(graph: &WeightedGraph<N, W>) -> Self {
+        CsrGraph::build(
+            graph.is_directed(),
+            graph.nodes().map(|u| {
+                (u.clone(), graph.out_edges(u).map(|e| (e.target.clone(), e.weight, e.id)))
+            }),
+        )
+    }
+}
+
+impl<N: Eq + Hash + Clone, W: Clone, EData> GraphBase for CsrGraph<N, W, EData> {
+    type Node = N;
+    type Weight = W;
+
+    fn is_directed(&self) -> bool {
+        self.is_directed
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = N> + '_ {
+        self.nodes.iter().cloned()
+    }
+
+    fn neighbors<'a>(&'a self, node: &N) -> impl Iterator<Item = N> + use<'a, N, W, EData> {
+        let row = match self.index.get(node) {
+            Some(&i) => self.neighbor_indices(i),
+            None => &[],
+        };
+        row.iter().map(|&v| self.nodes[v].clone())
+    }
+
+    fn out_edges<'a>(&'a self, node: &N) -> impl Iterator<Item = EdgeRef<N, W>> + use<'a, N, W, EData> {
+        let row = self.index.get(node).copied();
+        row.into_iter().flat_map(|i| self.row_edges(i)).map(|e| EdgeRef {
+            source: self.nodes[e.source].clone(),
+            target: self.nodes[e.target].clone(),
+            weight: e.weight,
+            id: e.id,
+        })
+    }
+
+    fn contains_node(&self, node: &N) -> bool {
+        self.index.contains_key(node)
+    }
+
+    fn node_index(&self, node: &N) -> Option<usize> {
+        self.index_of(node)
+    }
+
+    fn node_bound(&self) -> Option<usize> {
+        Some(self.nodes.len())
+    }
+}
+
+/// A `CsrGraph` addressed by dense node index, see `CsrGraph::indices`.
+#[derive(Debug, Clone, Copy)]
+pub struct CsrIndices<'a, N, W, EData = ()> {
+    csr: &'a CsrGraph<N, W, EData>,
+}
+
+impl<'a, N: Eq + Hash + Clone, W: Clone, EData> GraphBase for CsrIndices<'a, N, W, EData> {
+    type Node = usize;
+    type Weight = W;
+
+    fn is_directed(&self) -> bool {
+        self.csr.is_directed
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = usize> + '_ {
+        0..self.csr.node_count()
+    }
+
+    fn neighbors<'b>(&'b self, node: &usize) -> impl Iterator<Item = usize> + use<'a, 'b, N, W, EData> {
+        let row = if *node < self.csr.node_count() {
+            self.csr.neighbor_indices(*node)
+        } else {
+            &[]
+        };
+        row.iter().copied()
+    }
+
+    fn out_edges<'b>(&'b self, node: &usize) -> impl Iterator<Item = EdgeRef<usize, W>> + use<'a, 'b, N, W, EData> {
+        let row = (*node < self.csr.node_count()).then_some(*node);
+        row.into_iter().flat_map(|i| self.csr.row_edges(i))
+    }
+
+    fn node_index(&self, node: &usize) -> Option<usize> {
+        (*node < self.csr.node_count()).then_some(*node)
+    }
+
+    fn node_bound(&self) -> Option<usize> {
+        Some(self.csr.node_count())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algos::bfs::BfsExt;
+    use crate::algos::dfs::DfsExt;
+
+    #[test]
+    fn test_csr_matches_source_traversals() {
+        let mut graph = Graph::new(false);
+        for i in 1..64 {
+            graph.add_edge(i, 2 * i).unwrap();
+            graph.add_edge(i, 2 * i + 1).unwrap();
+        }
+        let csr = CsrGraph::from(&graph);
+
+        assert_eq!(csr.node_count(), 127);
+        assert_eq!(csr.edge_count(), 126);
+        assert_eq!(csr.bfs(1).unwrap(), graph.bfs(1).unwrap());
+        assert_eq!(csr.dfs(5).unwrap(), graph.dfs(5).unwrap());
+        assert_eq!(csr.shortest_path_bfs(2, 7).unwrap(), graph.shortest_path_bfs(2, 7).unwrap());
+    }
+
+    #[test]
+    fn test_csr_index_view() {
+        let mut graph = Graph::new(true);
+        graph.add_edge("a", "b").unwrap();
+        graph.add_edge("b", "c").unwrap();
+        let csr = CsrGraph::from(&graph);
+
+        let a = csr.index_of(&"a").unwrap();
+        let order: Vec<_> = csr.indices().bfs(a).unwrap().into_iter().map(|i| *csr.node(i)).collect();
+        assert_eq!(order, vec!["a", "b", "c"]);
+        // "c" only appears as a target but still gets an index
+        assert!(csr.index_of(&"c").is_some());
+        assert!(csr.index_of(&"z").is_none());
+    }
+
+    #[test]
+    fn test_csr_weights() {
+        let mut graph: WeightedGraph<i32, f64> = WeightedGraph::new(true);
+        graph.add_edge(1, 2, 0.25).unwrap();
+        graph.add_edge(1, 3, 4.0).unwrap();
+        let csr = CsrGraph::from(&graph);
+
+        let one = csr.index_of(&1).unwrap();
+        let targets: Vec<_> = csr.neighbor_indices(one).iter().map(|&i| *csr.node(i)).collect();
+        assert_eq!(targets, vec![2, 3]);
+        assert_eq!(csr.neighbor_weights(one), &[0.25, 4.0]);
+        let ids: Vec<_> = graph.out_edges(&1).map(|e| e.id).collect();
+        assert_eq!(csr.neighbor_edge_ids(one), ids.as_slice());
+        let mut weights: Vec<_> = csr.edges().map(|e| e.weight).collect();
+        weights.sort_by(f64::total_cmp);
+        assert_eq!(weights, vec![0.25, 4.0]);
+    }
+
+    #[test]
+    fn test_csr_with_data() {
+        let mut graph: Graph<&str, u8, &str> = Graph::attributed(true, Default::default());
+        graph.add_node_with_data("a", 1);
+        let ab = graph.add_edge_with_data("a", "b", "http").unwrap();
+        let ac = graph.add_edge("a", "c").unwrap();
+
+        let (csr, node_data) = CsrGraph::with_data(&graph);
+        let a = csr.index_of(&"a").unwrap();
+        assert_eq!(csr.neighbor_edge_ids(a), &[ab, ac]);
+        assert_eq!(csr.neighbor_weights(a), &[(), ()]);
+        assert_eq!(csr.edge_data(ab), Some(&"http"));
+        assert_eq!(csr.edge_data(ac), None);
+        assert_eq!(node_data[a], Some(1));
+        assert_eq!(node_data[csr.index_of(&"b").unwrap()], None);
+        assert_eq!(csr.bfs("a").unwrap(), graph.bfs("a").unwrap());
+
+        // the plain conversion keeps only the structure
+        let plain: CsrGraph<&str, ()> = CsrGraph::from(&graph);
+        assert_eq!(plain.neighbor_edge_ids(plain.index_of(&"a").unwrap()), &[ab, ac]);
+        assert_eq!(plain.edge_data(ab), None);
+    }
+}
@@ -0,0 +1,51 @@
+/// Handle for one edge, returned by `add_edge`.
+///
+/// Ids are never reused within a graph, so a handle keeps pointing at the
+/// same parallel edge even after other edges are removed. Both stored
+/// directions of an undirected edge share one id.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EdgeId(pub(crate) usize);
+
+impl EdgeId {
+    /// The raw number behind the id.
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// One edge as seen by iterators and traversals.
+///
+/// The graph types yield `EdgeRef<&N, W>` borrowing the endpoints; the
+/// `GraphBase` methods yield owned `EdgeRef<N, W>`. Unweighted graphs use
+/// `W = ()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EdgeRef<N, W = ()> {
+    pub source: N,
+    pub target: N,
+    pub weight: W,
+    pub id: EdgeId,
+}
+
+impl<N, W> EdgeRef<N, W> {
+    /// The same edge seen from the other end.
+    pub fn reversed(self) -> Self {
+        EdgeRef {
+            source: self.target,
+            target: self.source,
+            weight: self.weight,
+            id: self.id,
+        }
+    }
+}
+
+impl<N: Clone, W> EdgeRef<&N, W> {
+    /// Clones the borrowed endpoints into an owned `EdgeRef`.
+    pub fn cloned(self) -> EdgeRef<N, W> {
+        EdgeRef {
+            source: self.source.clone(),
+            target: self.target.clone(),
+            weight: self.weight,
+            id: self.id,
+        }
+    }
+}
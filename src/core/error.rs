@@ -0,0 +1,66 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::core::edge::EdgeId;
+
+/// Errors reported by graph mutations and algorithms.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GraphError {
+    /// The graph's `EdgePolicy` forbids parallel edges and the edge exists,
+    /// or an edge being restored has an id that is already in use.
+    DuplicateEdge,
+    /// A node being restored is already in the graph.
+    DuplicateNode,
+    /// A node being deleted by an `Edit` still has edges attached.
+    NodeHasEdges,
+    /// The graph's `EdgePolicy` forbids self-loops.
+    SelfLoop,
+    /// A node passed in is not part of the graph.
+    NodeNotFound,
+    /// No edge matches the given endpoints or id.
+    EdgeNotFound,
+    /// A shortest-path query reached a cycle of negative total weight.
+    NegativeCycle,
+    /// The operation needs an acyclic directed graph.
+    NotADag,
+    /// A weight is unusable, e.g. NaN.
+    InvalidWeight,
+    /// Summing weights along a path overflowed the weight type.
+    WeightOverflow,
+    /// Input text could not be parsed into a graph.
+    ParseError(String),
+}
+
+impl fmt::Display for GraphError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GraphError::DuplicateEdge => write!(f, "edge already exists and parallel edges are not allowed"),
+            GraphError::DuplicateNode => write!(f, "node already exists"),
+            GraphError::NodeHasEdges => write!(f, "node still has edges"),
+            GraphError::SelfLoop => write!(f, "self-loops are not allowed"),
+            GraphError::NodeNotFound => write!(f, "node not found"),
+            GraphError::EdgeNotFound => write!(f, "edge not found"),
+            GraphError::NegativeCycle => write!(f, "graph contains a negative cycle"),
+            GraphError::NotADag => write!(f, "graph is not a directed acyclic graph"),
+            GraphError::InvalidWeight => write!(f, "invalid edge weight"),
+            GraphError::WeightOverflow => write!(f, "path weight overflowed"),
+            GraphError::ParseError(msg) => write!(f, "parse error: {msg}"),
+        }
+    }
+}
+
+impl Error for GraphError {}
+
+/// Broken storage invariant found by `validate`, usually in a graph built
+/// with `from_adjacency_unchecked`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Violation<N> {
+    /// An undirected edge is stored under `source` but has no mirror entry
+    /// under `target`.
+    Asymmetric { source: N, target: N, id: EdgeId },
+    /// `target` appears in the adjacency list of `source` but has no list of
+    /// its own.
+    DanglingTarget { source: N, target: N, id: EdgeId },
+    /// A parallel edge although the graph's `EdgePolicy` forbids them.
+    DuplicateEdge { source: N, target: N, id: EdgeId },
+}
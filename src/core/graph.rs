@@ -1,23 +1,1003 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
 
+use crate::core::changelog::{Change, ChangeLog};
+use crate::core::edge::{EdgeId, EdgeRef};
+use crate::core::error::{GraphError, Violation};
+use crate::core::integrity::{self, Storage};
+use crate::core::policy::EdgePolicy;
+use crate::core::snapshot::{GraphSnapshot, SharedAdjacency, SharedPayloads};
+use crate::core::transaction::Transaction;
+use crate::core::weight::Weight;
+use crate::core::weightedgraph::WeightedGraph;
+
+/// Adjacency-list graph. `NData` and `EData` are optional payloads that can
+/// be attached to nodes and edges; graphs built with `new` carry none.
 #[derive(Debug,Clone)]
-pub struct Graph{
-    pub adj_list: HashMap<i32, Vec<i32>>,
-    pub is_directed: bool,
+pub struct Graph<N = i32, NData = (), EData = ()>{
+    /// Out-neighbors of each node with the id of the connecting edge.
+    /// Undirected edges appear in both endpoints' lists under the same id.
+    adj_list: SharedAdjacency<N, (N, EdgeId)>,
+    is_directed: bool,
+    policy: EdgePolicy,
+    /// Endpoints of every edge, in the orientation it was added.
+    edge_table: HashMap<EdgeId, (N, N)>,
+    next_edge_id: usize,
+    /// Incoming edges of a directed graph, kept only once
+    /// `enable_reverse_index` has been called.
+    rev_list: Option<SharedAdjacency<N, (N, EdgeId)>>,
+    /// Payloads of the nodes and edges that have one, shared with
+    /// snapshots until the next payload write.
+    node_data: SharedPayloads<N, NData>,
+    edge_data: SharedPayloads<EdgeId, EData>,
+    /// Version counter and, once enabled, the record of structural changes.
+    changes: ChangeLog<N>,
 }
 
-impl Graph {
+impl<N: Eq + Hash + Clone> Graph<N> {
     pub fn new(is_directed: bool) -> Self{
+        Graph::attributed(is_directed, EdgePolicy::default())
+    }
+
+    /// Creates an empty graph whose `add_edge` enforces `policy`.
+    pub fn with_policy(is_directed: bool, policy: EdgePolicy) -> Self {
+        Graph::attributed(is_directed, policy)
+    }
+
+    /// Builds a graph from `(u, v)` edge tuples, creating nodes as needed.
+    pub fn from_edges<I: IntoIterator<Item = (N, N)>>(is_directed: bool, edges: I) -> Self {
+        let mut graph = Graph::new(is_directed);
+        graph.extend(edges);
+        graph
+    }
+
+    /// Builds a graph from a plain adjacency map, e.g. one deserialized or
+    /// produced by older code, storing it as given without checking any
+    /// invariant. Every `u -> v` entry becomes an edge; in an undirected
+    /// graph it is paired with a `v -> u` entry into one edge when there is
+    /// one left to pair with. Run `validate` or `repair` on the result.
+    pub fn from_adjacency_unchecked(is_directed: bool, policy: EdgePolicy, adjacency: HashMap<N, Vec<N>>) -> Self {
+        let mut graph = Graph::with_policy(is_directed, policy);
+        let lists = integrity::assign_ids(is_directed, adjacency, |v| v, |_, _| true, |u, v| {
+            let id = EdgeId(graph.next_edge_id);
+            graph.next_edge_id += 1;
+            graph.edge_table.insert(id, (u.clone(), v.clone()));
+            id
+        });
+        for (u, out) in lists {
+            graph.adj_list.insert(u, out);
+        }
+        graph
+    }
+}
+
+// Payloads may be shared with snapshots, so everything that hands one out
+// by value or for writing needs them to be `Clone`.
+impl<N: Eq + Hash + Clone, NData: Clone, EData: Clone> Graph<N, NData, EData> {
+    /// Takes an O(1) read-only snapshot of the graph, payloads included,
+    /// that later mutations of the graph do not affect. While it is alive,
+    /// each write copies the O(log V) path to what it touches plus that
+    /// adjacency list or payload, never the whole graph. See `GraphSnapshot`.
+    pub fn snapshot(&self) -> GraphSnapshot<N, NData, EData> {
+        GraphSnapshot {
+            adj: self.adj_list.clone(),
+            rev: self.rev_list.clone(),
+            node_data: self.node_data.clone(),
+            edge_data: self.edge_data.clone(),
+            is_directed: self.is_directed,
+            edge_count: self.edge_table.len(),
+            version: self.version(),
+        }
+    }
+
+    /// Adds `node` if it is missing and attaches `data` to it, returning the
+    /// payload it replaced.
+    pub fn add_node_with_data(&mut self, node: N, data: NData) -> Option<NData> {
+        self.add_node(node.clone());
+        self.node_data.insert(node, data)
+    }
+
+    pub fn node_data_mut(&mut self, node: &N) -> Option<&mut NData> {
+        self.node_data.get_mut(node)
+    }
+
+    /// Detaches and returns the payload of `node`, leaving the node itself
+    /// in place.
+    pub fn take_node_data(&mut self, node: &N) -> Option<NData> {
+        self.node_data.remove(node)
+    }
+
+    pub fn edge_data_mut(&mut self, id: EdgeId) -> Option<&mut EData> {
+        self.edge_data.get_mut(&id)
+    }
+
+    /// Attaches `data` to an existing edge, returning the payload it
+    /// replaced.
+    pub fn set_edge_data(&mut self, id: EdgeId, data: EData) -> Result<Option<EData>, GraphError> {
+        if !self.edge_table.contains_key(&id) {
+            return Err(GraphError::EdgeNotFound);
+        }
+        Ok(self.edge_data.insert(id, data))
+    }
+
+    /// Detaches and returns the payload of an edge, leaving the edge itself
+    /// in place.
+    pub fn take_edge_data(&mut self, id: EdgeId) -> Option<EData> {
+        self.edge_data.remove(&id)
+    }
+
+    /// Starts a transaction: edits made through it are rolled back unless
+    /// it is committed.
+    pub fn begin(&mut self) -> Transaction<'_, Self> {
+        Transaction::new(self)
+    }
+}
+
+impl<N: Eq + Hash + Clone, NData, EData> Graph<N, NData, EData> {
+    /// Creates an empty graph whose nodes and edges can carry `NData` and
+    /// `EData` payloads.
+    pub fn attributed(is_directed: bool, policy: EdgePolicy) -> Self {
         Graph {
-            adj_list: HashMap::new(),
+            adj_list: SharedAdjacency::default(),
             is_directed,
+            policy,
+            edge_table: HashMap::new(),
+            next_edge_id: 0,
+            rev_list: None,
+            node_data: SharedPayloads::default(),
+            edge_data: SharedPayloads::default(),
+            changes: ChangeLog::new(),
+        }
+    }
+
+    pub fn policy(&self) -> EdgePolicy {
+        self.policy
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.is_directed
+    }
+
+    /// Starts maintaining an index of incoming edges so `predecessors` and
+    /// `in_degree` no longer scan the whole graph. Every later mutation keeps
+    /// it current. Undirected graphs don't need one and are left unchanged.
+    pub fn enable_reverse_index(&mut self) {
+        if !self.is_directed || self.rev_list.is_some() {
+            return;
+        }
+        let mut rev = SharedAdjacency::default();
+        for (u, out) in self.adj_list.iter() {
+            for (v, id) in out {
+                rev.entry_or_default(v.clone()).push((u.clone(), *id));
+            }
+        }
+        self.rev_list = Some(rev);
+    }
+
+    /// Drops the incoming-edge index, freeing its memory.
+    pub fn disable_reverse_index(&mut self) {
+        self.rev_list = None;
+    }
+
+    pub fn has_reverse_index(&self) -> bool {
+        self.rev_list.is_some()
+    }
+
+    /// Starts recording node and edge insertions and removals, so caches
+    /// derived from the graph can catch up through `change_log().since`
+    /// instead of recomputing. Payload updates are not recorded.
+    pub fn enable_change_log(&mut self) {
+        self.changes.start_recording();
+    }
+
+    /// Stops recording and drops the changes not drained yet. The version
+    /// keeps counting.
+    pub fn disable_change_log(&mut self) {
+        self.changes.stop_recording();
+    }
+
+    /// Number of structural changes made to the graph so far.
+    pub fn version(&self) -> u64 {
+        self.changes.version()
+    }
+
+    /// The change log, for reading deltas with `since` or subscribing.
+    pub fn change_log(&self) -> &ChangeLog<N> {
+        &self.changes
+    }
+
+    /// The change log, for draining deltas or managing observers.
+    pub fn change_log_mut(&mut self) -> &mut ChangeLog<N> {
+        &mut self.changes
+    }
+
+    /// Incoming adjacency of `node` when it can be read without scanning:
+    /// the neighbor list of an undirected graph or the reverse index of a
+    /// directed one. `None` means the caller has to scan.
+    pub(crate) fn incoming(&self, node: &N) -> Option<&[(N, EdgeId)]> {
+        let map = if self.is_directed { self.rev_list.as_ref()? } else { &self.adj_list };
+        Some(map.get(node).map_or(&[], Vec::as_slice))
+    }
+
+    /// Iterates over the nodes with an edge into `node`, once per edge.
+    /// Undirected graphs return the neighbors; directed graphs without a
+    /// reverse index scan every adjacency list.
+    pub fn predecessors<'a>(&'a self, node: &'a N) -> Box<dyn Iterator<Item = &'a N> + 'a> {
+        match self.incoming(node) {
+            Some(incoming) => Box::new(incoming.iter().map(|(u, _)| u)),
+            None => Box::new(self.adj_list.iter().flat_map(move |(u, out)| {
+                out.iter().filter(move |(v, _)| v == node).map(move |_| u)
+            })),
+        }
+    }
+
+    /// Adds the edge `u -> v` (and `v -> u` if undirected), creating missing
+    /// nodes. Fails if the graph's `EdgePolicy` rejects the edge.
+    pub fn add_edge(&mut self, u: N, v: N) -> Result<EdgeId, GraphError> {
+        self.policy.check(&u, &v, || self.has_edge(&u, &v))?;
+        Ok(self.insert_edge(u, v))
+    }
+
+    /// `add_edge` without the policy check, for callers that already know
+    /// the edge is acceptable.
+    pub(crate) fn insert_edge(&mut self, u: N, v: N) -> EdgeId {
+        let id = EdgeId(self.next_edge_id);
+        self.insert_edge_with_id(u, v, id);
+        id
+    }
+
+    /// Stores an edge under an id chosen by the caller, for conversions
+    /// that keep the source graph's ids. `id` must not be in use.
+    pub(crate) fn insert_edge_with_id(&mut self, u: N, v: N, id: EdgeId) {
+        self.add_node(u.clone());
+        self.add_node(v.clone());
+        self.next_edge_id = self.next_edge_id.max(id.0 + 1);
+        self.edge_table.insert(id, (u.clone(), v.clone()));
+        self.changes.record(|| Change::EdgeAdded(EdgeRef { source: u.clone(), target: v.clone(), weight: (), id }));
+
+        self.adj_list.entry_or_default(u.clone()).push((v.clone(), id));
+        if !self.is_directed {
+            self.adj_list.entry_or_default(v).push((u, id));
+        } else if let Some(rev) = &mut self.rev_list {
+            rev.entry_or_default(v).push((u, id));
+        }
+    }
+
+    /// Adds an isolated node. Returns `false` if it was already present.
+    pub fn add_node(&mut self, node: N) -> bool {
+        if !self.adj_list.add_key(node.clone()) {
+            return false;
+        }
+        self.changes.record(|| Change::NodeAdded(node));
+        true
+    }
+
+    /// `add_edge` that also attaches `data` to the new edge.
+    pub fn add_edge_with_data(&mut self, u: N, v: N, data: EData) -> Result<EdgeId, GraphError> {
+        let id = self.add_edge(u, v)?;
+        self.edge_data.set(id, data);
+        Ok(id)
+    }
+
+    pub fn node_data(&self, node: &N) -> Option<&NData> {
+        self.node_data.get(node)
+    }
+
+    pub fn edge_data(&self, id: EdgeId) -> Option<&EData> {
+        self.edge_data.get(&id)
+    }
+
+    /// Copies the graph, edge ids and change log included, converting every
+    /// payload with `node_map` and `edge_map`.
+    pub fn map_data<NData2, EData2, F, G>(&self, mut node_map: F, mut edge_map: G) -> Graph<N, NData2, EData2>
+    where
+        F: FnMut(&N, &NData) -> NData2,
+        G: FnMut(EdgeRef<&N>, &EData) -> EData2,
+    {
+        let node_data = self.node_data.iter().map(|(n, d)| (n.clone(), node_map(n, d))).collect();
+        let edge_data = self.edge_data.iter().filter_map(|(id, d)| Some((*id, edge_map(self.edge(*id)?, d)))).collect();
+        Graph {
+            adj_list: self.adj_list.clone(),
+            is_directed: self.is_directed,
+            policy: self.policy,
+            edge_table: self.edge_table.clone(),
+            next_edge_id: self.next_edge_id,
+            rev_list: self.rev_list.clone(),
+            node_data,
+            edge_data,
+            changes: self.changes.clone(),
         }
     }
 
-    pub fn add_edge(&mut self, u: i32, v: i32) {
-        self.adj_list.entry(u).or_insert(Vec::new()).push(v);
+    pub fn contains_node(&self, node: &N) -> bool {
+        self.adj_list.contains_key(node)
+    }
+
+    /// Whether at least one `u -> v` edge exists.
+    pub fn has_edge(&self, u: &N, v: &N) -> bool {
+        self.adj_list.get(u).is_some_and(|out| out.iter().any(|(x, _)| x == v))
+    }
+
+    pub fn contains_edge(&self, id: EdgeId) -> bool {
+        self.edge_table.contains_key(&id)
+    }
+
+    /// Looks up an edge by id.
+    pub fn edge(&self, id: EdgeId) -> Option<EdgeRef<&N>> {
+        let (u, v) = self.edge_table.get(&id)?;
+        Some(EdgeRef { source: u, target: v, weight: (), id })
+    }
+
+    /// Every `u -> v` edge; more than one if parallel edges are allowed.
+    pub fn edges_connecting<'a>(&'a self, u: &'a N, v: &'a N) -> impl Iterator<Item = EdgeRef<&'a N>> {
+        self.out_edges(u).filter(move |e| e.target == v)
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.adj_list.len()
+    }
+
+    /// Number of edges, counting each undirected edge once.
+    pub fn edge_count(&self) -> usize {
+        self.edge_table.len()
+    }
+
+    /// Number of edges leaving `node`. In an undirected graph this is the
+    /// degree, with a self-loop counted twice.
+    pub fn out_degree(&self, node: &N) -> Result<usize, GraphError> {
+        self.adj_list.get(node).map(Vec::len).ok_or(GraphError::NodeNotFound)
+    }
+
+    /// Number of edges entering `node`. Equal to `out_degree` for undirected
+    /// graphs; directed graphs without a reverse index scan every adjacency
+    /// list.
+    pub fn in_degree(&self, node: &N) -> Result<usize, GraphError> {
+        if !self.contains_node(node) {
+            return Err(GraphError::NodeNotFound);
+        }
+        Ok(self.predecessors(node).count())
+    }
+
+    /// Total degree: `in_degree + out_degree` for directed graphs, the
+    /// number of incident edge ends for undirected ones.
+    pub fn degree(&self, node: &N) -> Result<usize, GraphError> {
+        if self.is_directed {
+            Ok(self.in_degree(node)? + self.out_degree(node)?)
+        } else {
+            self.out_degree(node)
+        }
+    }
+
+    /// Iterates over every node, in no particular order.
+    pub fn nodes(&self) -> impl Iterator<Item = &N> {
+        self.adj_list.keys()
+    }
+
+    /// Iterates over the out-neighbors of `node`; empty if it is unknown.
+    pub fn neighbors<'a>(&'a self, node: &N) -> impl Iterator<Item = &'a N> + use<'a, N, NData, EData> {
+        self.adj_list.get(node).into_iter().flatten().map(|(v, _)| v)
+    }
+
+    /// Iterates over the edges leaving `node`; empty if it is unknown.
+    pub fn out_edges<'a>(&'a self, node: &N) -> impl Iterator<Item = EdgeRef<&'a N>> + use<'a, N, NData, EData> {
+        self.adj_list.get_key_value(node).into_iter().flat_map(|(u, out)| {
+            out.iter().map(move |(v, id)| EdgeRef { source: u, target: v, weight: (), id: *id })
+        })
+    }
+
+    /// Iterates over every edge, in the orientation it was added, reporting
+    /// each undirected edge once.
+    pub fn edges(&self) -> impl Iterator<Item = EdgeRef<&N>> {
+        self.edge_table.iter().map(|(id, (u, v))| EdgeRef { source: u, target: v, weight: (), id: *id })
+    }
+
+    /// Removes one `u -> v` edge, and its mirror entry if the graph is
+    /// undirected. Returns the id of the removed edge.
+    pub fn remove_edge(&mut self, u: &N, v: &N) -> Result<EdgeId, GraphError> {
+        if !self.contains_node(u) || !self.contains_node(v) {
+            return Err(GraphError::NodeNotFound);
+        }
+        let (_, id) = *self.adj_list.get(u).into_iter().flatten().find(|(x, _)| x == v).ok_or(GraphError::EdgeNotFound)?;
+        self.remove_edge_by_id(id)?;
+        Ok(id)
+    }
+
+    /// Removes the edge with the given id from every list it is stored in,
+    /// returning it. Its payload, if any, is dropped.
+    pub fn remove_edge_by_id(&mut self, id: EdgeId) -> Result<EdgeRef<N>, GraphError> {
+        let (u, v) = self.edge_table.remove(&id).ok_or(GraphError::EdgeNotFound)?;
+        self.edge_data.discard(&id);
+        remove_first(self.adj_list.get_mut(&u), |(_, x)| *x == id);
         if !self.is_directed {
-            self.adj_list.entry(v).or_insert(Vec::new()).push(u);
+            remove_first(self.adj_list.get_mut(&v), |(_, x)| *x == id);
+        } else if let Some(rev) = &mut self.rev_list {
+            remove_first(rev.get_mut(&v), |(_, x)| *x == id);
+        }
+        let removed = EdgeRef { source: u, target: v, weight: (), id };
+        self.changes.record(|| Change::EdgeRemoved(removed.clone()));
+        Ok(removed)
+    }
+
+    /// Removes `node` together with every edge touching it, in both
+    /// directions, and their payloads. Returns the number of edges removed.
+    pub fn remove_node(&mut self, node: &N) -> Result<usize, GraphError> {
+        let out = self.adj_list.get(node).ok_or(GraphError::NodeNotFound)?;
+        let mut incident: HashSet<EdgeId> = out.iter().map(|(_, id)| *id).collect();
+        incident.extend(self.in_edge_ids(node));
+        for id in &incident {
+            let _ = self.remove_edge_by_id(*id);
+        }
+        self.adj_list.remove(node);
+        self.node_data.discard(node);
+        if let Some(rev) = &mut self.rev_list {
+            rev.remove(node);
+        }
+        self.changes.record(|| Change::NodeRemoved(node.clone()));
+        Ok(incident.len())
+    }
+
+    fn in_edge_ids(&self, node: &N) -> Vec<EdgeId> {
+        match self.incoming(node) {
+            Some(incoming) => incoming.iter().map(|(_, id)| *id).collect(),
+            None => self.adj_list.values().flatten().filter(|(v, _)| v == node).map(|(_, id)| *id).collect(),
+        }
+    }
+
+    /// Removes every edge but keeps all nodes. Returns the number of edges
+    /// removed.
+    pub fn clear_edges(&mut self) -> usize {
+        let removed = self.edge_table.len();
+        if self.changes.is_observed() {
+            let edges: Vec<EdgeRef<N>> = self.edges_in_id_order().into_iter().map(EdgeRef::cloned).collect();
+            for e in edges {
+                self.changes.record(|| Change::EdgeRemoved(e));
+            }
+        } else {
+            self.changes.skip(removed);
+        }
+        self.edge_table.clear();
+        self.edge_data.clear();
+        self.adj_list.clear_lists();
+        if let Some(rev) = &mut self.rev_list {
+            rev.clear();
+        }
+        removed
+    }
+
+    /// Keeps only the nodes for which `keep` returns `true`, dropping the
+    /// others along with their incident edges. Returns the number of nodes
+    /// removed.
+    pub fn retain_nodes<F: FnMut(&N) -> bool>(&mut self, mut keep: F) -> usize {
+        let doomed: Vec<N> = self.adj_list.keys().filter(|n| !keep(n)).cloned().collect();
+        for node in &doomed {
+            let _ = self.remove_node(node);
         }
+        doomed.len()
+    }
+
+    /// Keeps only the edges for which `keep(source, target)` returns `true`.
+    /// Each undirected edge is tested once, in the orientation it was added,
+    /// and both of its entries are dropped together. Returns the number of
+    /// edges removed.
+    pub fn retain_edges<F: FnMut(&N, &N) -> bool>(&mut self, mut keep: F) -> usize {
+        let doomed: Vec<EdgeId> = self.edges().filter(|e| !keep(e.source, e.target)).map(|e| e.id).collect();
+        for id in &doomed {
+            let _ = self.remove_edge_by_id(*id);
+        }
+        doomed.len()
+    }
+
+    /// Copies the graph into a `WeightedGraph`, giving every edge the weight
+    /// `weight` computes for it. Nodes, edge ids, the policy and the reverse
+    /// index carry over; payloads do not, since `WeightedGraph` has none,
+    /// though `weight` may read them to derive each weight. Fails with
+    /// `InvalidWeight` if `weight` returns an invalid weight.
+    pub fn to_weighted<W, F>(&self, mut weight: F) -> Result<WeightedGraph<N, W>, GraphError>
+    where
+        W: Weight,
+        F: FnMut(EdgeRef<&N>) -> W,
+    {
+        let mut weighted = WeightedGraph::with_policy(self.is_directed, self.policy);
+        if self.has_reverse_index() {
+            weighted.enable_reverse_index();
+        }
+        for node in self.nodes() {
+            weighted.add_node(node.clone());
+        }
+        for e in self.edges_in_id_order() {
+            let w = weight(e);
+            if !w.is_valid() {
+                return Err(GraphError::InvalidWeight);
+            }
+            weighted.insert_edge_with_id(e.source.clone(), e.target.clone(), w, e.id);
+        }
+        Ok(weighted)
+    }
+
+    /// Consumes the graph, returning its edges as `(u, v)` tuples in the
+    /// order they were added, each undirected edge once. Isolated nodes and
+    /// payloads are dropped.
+    pub fn into_edges(self) -> Vec<(N, N)> {
+        let mut edges: Vec<_> = self.edge_table.into_iter().collect();
+        edges.sort_by_key(|(id, _)| *id);
+        edges.into_iter().map(|(_, uv)| uv).collect()
+    }
+
+    fn edges_in_id_order(&self) -> Vec<EdgeRef<&N>> {
+        let mut edges: Vec<_> = self.edges().collect();
+        edges.sort_by_key(|e| e.id);
+        edges
+    }
+
+    /// Checks the storage invariants `add_edge` maintains: every undirected
+    /// edge is stored under both endpoints, every target has an adjacency
+    /// list, and parallel edges only exist if the policy allows them.
+    /// Violations are reported in edge id order.
+    pub fn validate(&self) -> Result<(), Vec<Violation<N>>> {
+        integrity::validate(self)
+    }
+
+    /// Fixes everything `validate` reports: missing targets get an empty
+    /// adjacency list, undirected edges get their missing mirror entry, and
+    /// forbidden parallel edges are removed, keeping the oldest. Every fix
+    /// is recorded like any other change; a restored mirror shows up as the
+    /// edge being removed and added again under the same id. Returns the
+    /// violations that were repaired.
+    pub fn repair(&mut self) -> Vec<Violation<N>> {
+        integrity::repair(self)
+    }
+}
+
+impl<N: Eq + Hash + Clone, NData, EData> Storage<N> for Graph<N, NData, EData> {
+    fn is_directed(&self) -> bool {
+        self.is_directed
+    }
+
+    fn policy(&self) -> EdgePolicy {
+        self.policy
+    }
+
+    fn has_list(&self, node: &N) -> bool {
+        self.adj_list.contains_key(node)
+    }
+
+    fn edge_endpoints<'a>(&'a self) -> impl Iterator<Item = (EdgeId, &'a N, &'a N)>
+    where
+        N: 'a,
+    {
+        self.edge_table.iter().map(|(id, (u, v))| (*id, u, v))
+    }
+
+    fn stored_ids(&self) -> impl Iterator<Item = EdgeId> {
+        self.adj_list.values().flatten().map(|(_, id)| *id)
+    }
+
+    fn add_node(&mut self, node: N) -> bool {
+        Graph::add_node(self, node)
+    }
+
+    fn restore_edge(&mut self, id: EdgeId) {
+        let Some((u, v)) = self.edge_table.remove(&id) else {
+            return;
+        };
+        remove_first(self.adj_list.get_mut(&u), |(_, x)| *x == id);
+        remove_first(self.adj_list.get_mut(&v), |(_, x)| *x == id);
+        let removed = EdgeRef { source: u.clone(), target: v.clone(), weight: (), id };
+        self.changes.record(|| Change::EdgeRemoved(removed));
+        self.insert_edge_with_id(u, v, id);
+    }
+
+    fn remove_edge(&mut self, id: EdgeId) {
+        let _ = self.remove_edge_by_id(id);
+    }
+
+    fn rebuild_reverse_index(&mut self) {
+        if self.rev_list.is_some() {
+            self.rev_list = None;
+            self.enable_reverse_index();
+        }
+    }
+}
+
+/// Adds every `(u, v)` edge. Panics on the first edge the graph's
+/// `EdgePolicy` rejects, leaving the edges before it added; call `add_edge`
+/// to handle rejections instead.
+impl<N: Eq + Hash + Clone, NData, EData> Extend<(N, N)> for Graph<N, NData, EData> {
+    fn extend<I: IntoIterator<Item = (N, N)>>(&mut self, edges: I) {
+        for (u, v) in edges {
+            if let Err(err) = self.add_edge(u, v) {
+                panic!("cannot extend graph: {err}");
+            }
+        }
+    }
+}
+
+/// Collects into a directed graph; use `Graph::from_edges` for an
+/// undirected one.
+impl<N: Eq + Hash + Clone> FromIterator<(N, N)> for Graph<N> {
+    fn from_iter<I: IntoIterator<Item = (N, N)>>(edges: I) -> Self {
+        Graph::from_edges(true, edges)
+    }
+}
+
+/// Builds a directed graph from an array of edges.
+impl<N: Eq + Hash + Clone, const K: usize> From<[(N, N); K]> for Graph<N> {
+    fn from(edges: [(N, N); K]) -> Self {
+        Graph::from_edges(true, edges)
+    }
+}
+
+/// Removes the first element matching `pred` from an optional adjacency list.
+pub(crate) fn remove_first<T, F: Fn(&T) -> bool>(list: Option<&mut Vec<T>>, pred: F) -> bool {
+    let Some(list) = list else {
+        return false;
+    };
+    match list.iter().position(pred) {
+        Some(i) => {
+            list.remove(i);
+            true
+        }
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algos::bfs::BfsExt;
+    use crate::core::changelog::Change;
+
+    #[test]
+    fn test_remove_edge_undirected() {
+        let mut graph = Graph::new(false);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(2, 3).unwrap();
+
+        assert!(graph.remove_edge(&2, &1).is_ok());
+        assert_eq!(graph.neighbors(&1).collect::<Vec<_>>(), vec![&2]);
+        assert_eq!(graph.neighbors(&2).collect::<Vec<_>>(), vec![&1, &3]);
+        assert_eq!(graph.remove_edge(&1, &3), Err(GraphError::EdgeNotFound));
+        assert_eq!(graph.remove_edge(&7, &8), Err(GraphError::NodeNotFound));
+    }
+
+    #[test]
+    fn test_remove_self_loop_undirected() {
+        let mut graph = Graph::new(false);
+        graph.add_edge(1, 1).unwrap();
+        graph.add_edge(1, 2).unwrap();
+
+        assert!(graph.remove_edge(&1, &1).is_ok());
+        assert_eq!(graph.neighbors(&1).collect::<Vec<_>>(), vec![&2]);
+    }
+
+    #[test]
+    fn test_remove_node() {
+        let mut directed = Graph::new(true);
+        directed.add_edge(1, 2).unwrap();
+        directed.add_edge(3, 2).unwrap();
+        directed.add_edge(2, 4).unwrap();
+        directed.add_edge(2, 2).unwrap();
+
+        assert_eq!(directed.remove_node(&2), Ok(4));
+        assert_eq!(directed.remove_node(&2), Err(GraphError::NodeNotFound));
+        assert!(directed.out_degree(&1) == Ok(0));
+        assert!(directed.out_degree(&3) == Ok(0));
+
+        let mut undirected = Graph::new(false);
+        undirected.add_edge(1, 2).unwrap();
+        undirected.add_edge(2, 3).unwrap();
+        undirected.add_edge(2, 2).unwrap();
+
+        assert_eq!(undirected.remove_node(&2), Ok(3));
+        assert!(undirected.out_degree(&1) == Ok(0));
+        assert!(undirected.out_degree(&3) == Ok(0));
+    }
+
+    #[test]
+    fn test_reverse_index() {
+        let mut graph = Graph::new(true);
+        graph.add_edge(1, 3).unwrap();
+        graph.enable_reverse_index();
+        graph.add_edge(2, 3).unwrap();
+        graph.add_edge(3, 4).unwrap();
+        graph.add_edge(2, 3).unwrap();
+
+        let mut preds: Vec<_> = graph.predecessors(&3).copied().collect();
+        preds.sort();
+        assert_eq!(preds, vec![1, 2, 2]);
+        assert_eq!(graph.in_degree(&3), Ok(3));
+
+        assert!(graph.remove_edge(&2, &3).is_ok());
+        assert_eq!(graph.in_degree(&3), Ok(2));
+        assert_eq!(graph.retain_edges(|&u, _| u != 1), 1);
+        assert_eq!(graph.predecessors(&3).collect::<Vec<_>>(), vec![&2]);
+
+        assert_eq!(graph.remove_node(&3), Ok(2));
+        assert_eq!(graph.in_degree(&4), Ok(0));
+        assert!(graph.out_degree(&2) == Ok(0));
+
+        // the index agrees with a full scan
+        graph.add_edge(4, 1).unwrap();
+        graph.add_edge(1, 1).unwrap();
+        let mut unindexed = graph.clone();
+        unindexed.disable_reverse_index();
+        for n in [1, 2, 4] {
+            assert_eq!(graph.in_degree(&n), unindexed.in_degree(&n));
+        }
+    }
+
+    #[test]
+    fn test_simple_policy() {
+        let mut graph = Graph::with_policy(false, EdgePolicy::SIMPLE);
+        graph.add_edge(1, 2).unwrap();
+
+        assert_eq!(graph.add_edge(1, 2), Err(GraphError::DuplicateEdge));
+        assert_eq!(graph.add_edge(2, 1), Err(GraphError::DuplicateEdge));
+        assert_eq!(graph.add_edge(3, 3), Err(GraphError::SelfLoop));
+        // rejected edges leave no trace
+        assert_eq!(graph.edge_count(), 1);
+        assert!(!graph.contains_node(&3));
+
+        let mut directed = Graph::with_policy(true, EdgePolicy { allow_parallel: false, allow_self_loops: true });
+        directed.add_edge(1, 2).unwrap();
+        directed.add_edge(2, 1).unwrap();
+        directed.add_edge(1, 1).unwrap();
+        assert_eq!(directed.add_edge(1, 1), Err(GraphError::DuplicateEdge));
+        assert_eq!(directed.policy(), EdgePolicy { allow_parallel: false, allow_self_loops: true });
+    }
+
+    #[test]
+    fn test_multigraph_policy() {
+        let mut graph = Graph::new(false);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 1).unwrap();
+
+        assert_eq!(graph.policy(), EdgePolicy::MULTIGRAPH);
+        assert_eq!(graph.edge_count(), 3);
+        assert_eq!(graph.degree(&1), Ok(4));
+        assert_eq!(graph.degree(&2), Ok(2));
+    }
+
+    #[test]
+    fn test_nodes_and_counts() {
+        let mut graph = Graph::new(true);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 3).unwrap();
+        graph.add_edge(3, 1).unwrap();
+        assert!(graph.add_node(4));
+        assert!(!graph.add_node(4));
+
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.edge_count(), 3);
+        assert!(graph.contains_node(&2));
+        assert!(graph.has_edge(&3, &1));
+        assert!(!graph.has_edge(&2, &1));
+        assert_eq!(graph.out_degree(&1), Ok(2));
+        assert_eq!(graph.in_degree(&1), Ok(1));
+        assert_eq!(graph.degree(&1), Ok(3));
+        assert_eq!(graph.degree(&4), Ok(0));
+
+        let mut nodes: Vec<_> = graph.nodes().copied().collect();
+        nodes.sort();
+        assert_eq!(nodes, vec![1, 2, 3, 4]);
+        assert_eq!(graph.neighbors(&1).collect::<Vec<_>>(), vec![&2, &3]);
+    }
+
+    #[test]
+    fn test_undirected_edges_once() {
+        let mut graph = Graph::new(false);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(2, 3).unwrap();
+        graph.add_edge(3, 3).unwrap();
+
+        assert_eq!(graph.edge_count(), 4);
+        assert_eq!(graph.edges().count(), 4);
+        assert_eq!(graph.degree(&3), Ok(3));
+        assert_eq!(graph.in_degree(&2), Ok(3));
+        let loops = graph.edges().filter(|e| e.source == e.target).count();
+        assert_eq!(loops, 1);
+    }
+
+    #[test]
+    fn test_clear_and_retain() {
+        let mut graph = Graph::new(false);
+        for i in 1..5 {
+            graph.add_edge(i, i + 1).unwrap();
+        }
+
+        assert_eq!(graph.retain_edges(|u, v| u + v != 5), 1);
+        assert!(!graph.has_edge(&2, &3));
+        assert!(!graph.has_edge(&3, &2));
+
+        assert_eq!(graph.retain_nodes(|&n| n != 4), 1);
+        assert!(graph.out_degree(&5) == Ok(0));
+        assert!(!graph.has_edge(&3, &4));
+
+        assert_eq!(graph.clear_edges(), 1);
+        assert_eq!(graph.node_count(), 4);
+        assert!(graph.nodes().all(|n| graph.out_degree(n) == Ok(0)));
+    }
+
+    #[test]
+    fn test_node_and_edge_data() {
+        let mut graph: Graph<&str, &str, &str> = Graph::attributed(false, EdgePolicy::default());
+        assert_eq!(graph.add_node_with_data("api", "team-a"), None);
+        assert_eq!(graph.add_node_with_data("api", "team-b"), Some("team-a"));
+        let http = graph.add_edge_with_data("api", "db", "http").unwrap();
+        let grpc = graph.add_edge_with_data("api", "db", "grpc").unwrap();
+        let bare = graph.add_edge("db", "cache").unwrap();
+
+        assert_eq!(graph.node_data(&"api"), Some(&"team-b"));
+        assert_eq!(graph.node_data(&"db"), None);
+        assert_eq!(graph.edge_data(bare), None);
+        assert_eq!(graph.set_edge_data(bare, "tcp"), Ok(None));
+        *graph.edge_data_mut(grpc).unwrap() = "grpc+tls";
+
+        // Removing one parallel edge leaves the other's payload alone.
+        graph.remove_edge_by_id(http).unwrap();
+        assert_eq!(graph.edge_data(http), None);
+        assert_eq!(graph.edge_data(grpc), Some(&"grpc+tls"));
+        assert_eq!(graph.set_edge_data(http, "http"), Err(GraphError::EdgeNotFound));
+        assert_eq!(graph.edge_data(http), None);
+
+        let copy = graph.clone();
+        graph.remove_node(&"db").unwrap();
+        assert_eq!(graph.edge_data(grpc), None);
+        assert_eq!(graph.edge_data(bare), None);
+        assert_eq!(copy.edge_data(bare), Some(&"tcp"));
+        assert_eq!(graph.take_node_data(&"api"), Some("team-b"));
+        assert!(graph.contains_node(&"api"));
+    }
+
+    #[test]
+    fn test_map_data_keeps_ids() {
+        let mut graph: Graph<i32, String, u32> = Graph::attributed(true, EdgePolicy::default());
+        graph.add_node_with_data(1, "one".to_string());
+        let id = graph.add_edge_with_data(1, 2, 40).unwrap();
+
+        let mapped = graph.map_data(|_, name| name.len(), |e, cost| (*e.source, cost + 2));
+        assert_eq!(mapped.node_data(&1), Some(&3));
+        assert_eq!(mapped.edge_data(id), Some(&(1, 42)));
+        assert_eq!(mapped.edge(id).map(|e| (*e.source, *e.target)), Some((1, 2)));
+    }
+
+    #[test]
+    fn test_validate_and_repair_undirected() {
+        let adjacency = HashMap::from([(1, vec![2, 2, 3]), (2, vec![1, 1]), (3, vec![]), (4, vec![9])]);
+        let mut graph = Graph::from_adjacency_unchecked(false, EdgePolicy::SIMPLE, adjacency);
+        graph.enable_change_log();
+
+        let violations = graph.validate().unwrap_err();
+        let count = |f: fn(&Violation<i32>) -> bool| violations.iter().filter(|v| f(v)).count();
+        assert_eq!(count(|v| matches!(v, Violation::Asymmetric { .. })), 2);
+        assert_eq!(count(|v| matches!(v, Violation::DanglingTarget { source: 4, target: 9, .. })), 1);
+        assert_eq!(count(|v| matches!(v, Violation::DuplicateEdge { .. })), 1);
+
+        assert_eq!(graph.repair().len(), 4);
+        assert_eq!(graph.validate(), Ok(()));
+        assert!(graph.repair().is_empty());
+        let deltas = graph.change_log().since(0).unwrap();
+        assert_eq!(deltas.len(), 6);
+        assert_eq!(graph.version(), 6);
+        assert_eq!(deltas.iter().filter(|d| matches!(d.change, Change::EdgeAdded(_))).count(), 2);
+        assert_eq!(graph.edge_count(), 3);
+        assert_eq!(graph.degree(&2), Ok(1));
+        assert_eq!(graph.neighbors(&3).collect::<Vec<_>>(), vec![&1]);
+        assert_eq!(graph.neighbors(&9).collect::<Vec<_>>(), vec![&4]);
+    }
+
+    #[test]
+    fn test_validate_and_repair_directed() {
+        let adjacency = HashMap::from([(1, vec![2, 2]), (3, vec![3])]);
+        let mut graph = Graph::from_adjacency_unchecked(true, EdgePolicy::MULTIGRAPH, adjacency);
+        graph.enable_reverse_index();
+
+        let violations = graph.validate().unwrap_err();
+        assert_eq!(violations.len(), 2);
+        assert!(violations.iter().all(|v| matches!(v, Violation::DanglingTarget { source: 1, target: 2, .. })));
+
+        graph.repair();
+        assert_eq!(graph.validate(), Ok(()));
+        assert_eq!(graph.in_degree(&2), Ok(2));
+        assert_eq!(graph.edge_count(), 3);
+
+        let mut built = Graph::new(false);
+        built.add_edge(1, 1).unwrap();
+        built.add_edge(1, 2).unwrap();
+        built.add_edge(2, 1).unwrap();
+        assert_eq!(built.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_edge_tuple_conversions() {
+        let graph: Graph = [(1, 2), (2, 3), (3, 1)].into();
+        assert!(graph.is_directed());
+        assert_eq!(graph.edge_count(), 3);
+
+        let mut undirected = Graph::from_edges(false, vec![("a", "b"), ("b", "c")]);
+        undirected.extend([("c", "a"), ("a", "a")]);
+        assert_eq!(undirected.degree(&"a"), Ok(4));
+        assert_eq!(undirected.clone().into_edges(), vec![("a", "b"), ("b", "c"), ("c", "a"), ("a", "a")]);
+
+        let mut simple = Graph::with_policy(true, EdgePolicy::SIMPLE);
+        simple.extend([(1, 2), (2, 3)]);
+        assert_eq!(simple.clone().into_edges(), vec![(1, 2), (2, 3)]);
+        let rejected = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| simple.extend([(3, 3)])));
+        assert!(rejected.is_err());
+
+        let collected: Graph<u8> = (0..4).map(|i| (i, i + 1)).collect();
+        let round_trip = Graph::from_edges(true, collected.clone().into_edges());
+        assert_eq!(round_trip.bfs(0).unwrap(), collected.bfs(0).unwrap());
+    }
+
+    #[test]
+    fn test_to_weighted_keeps_ids() {
+        let mut graph: Graph<&str, (), u32> = Graph::attributed(false, EdgePolicy::default());
+        let ab = graph.add_edge_with_data("a", "b", 7).unwrap();
+        let bc = graph.add_edge("b", "c").unwrap();
+        graph.add_node("d");
+        graph.remove_edge_by_id(ab).unwrap();
+        let ab = graph.add_edge_with_data("a", "b", 4).unwrap();
+
+        let weighted = graph.to_weighted(|e| graph.edge_data(e.id).copied().unwrap_or(1)).unwrap();
+        assert_eq!(weighted.weight(ab), Some(4));
+        assert_eq!(weighted.weight(bc), Some(1));
+        assert_eq!(weighted.node_count(), 4);
+        assert_eq!(weighted.path_weight(&["a", "b", "c"]), Ok(5));
+        let next = weighted.clone().add_edge("c", "d", 1).unwrap();
+        assert!(next.index() > ab.index());
+
+        assert_eq!(graph.to_weighted(|_| f64::NAN).unwrap_err(), GraphError::InvalidWeight);
+
+        // WeightedGraph has no payloads, so the round trip drops them
+        let back = weighted.to_unweighted();
+        assert_eq!(back.edge_count(), 2);
+        assert_eq!(back.edge_data(ab), None);
+    }
+
+    #[test]
+    fn test_change_log() {
+        let mut graph = Graph::new(false);
+        graph.add_edge(1, 2).unwrap();
+        let seen = graph.version();
+        assert_eq!(seen, 3);
+        assert!(graph.change_log().since(seen).is_none());
+
+        graph.enable_change_log();
+        let e = graph.add_edge(2, 3).unwrap();
+        graph.remove_node(&1).unwrap();
+        let changes: Vec<_> = graph.change_log().since(seen).unwrap().iter().map(|d| d.change.clone()).collect();
+        assert_eq!(changes[0], Change::NodeAdded(3));
+        assert_eq!(changes[1], Change::EdgeAdded(EdgeRef { source: 2, target: 3, weight: (), id: e }));
+        assert!(matches!(changes[2], Change::EdgeRemoved(EdgeRef { source: 1, target: 2, .. })));
+        assert_eq!(changes[3], Change::NodeRemoved(1));
+        assert_eq!(graph.version(), seen + 4);
+
+        let drained = graph.change_log_mut().drain_since(seen + 2).unwrap();
+        assert_eq!(drained.iter().map(|d| d.version).collect::<Vec<_>>(), [6, 7]);
+        assert!(graph.change_log().since(seen).is_none());
+        assert_eq!(graph.clear_edges(), 1);
+        assert_eq!(graph.change_log().since(7).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_change_observers() {
+        use std::sync::{Arc, Mutex};
+
+        let mut graph: Graph<&str> = Graph::new(true);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Arc::clone(&seen);
+        let id = graph.change_log_mut().subscribe(move |d| sink.lock().unwrap().push(d.change.clone()));
+
+        graph.add_edge("a", "b").unwrap();
+        graph.clone().add_node("c");
+        assert!(graph.change_log_mut().unsubscribe(id));
+        graph.add_node("d");
+        assert_eq!(seen.lock().unwrap().len(), 3);
+        assert!(!graph.change_log().is_recording());
+        assert_eq!(graph.version(), 4);
     }
 }
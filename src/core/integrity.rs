@@ -0,0 +1,122 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+use crate::core::edge::EdgeId;
+use crate::core::error::Violation;
+use crate::core::policy::EdgePolicy;
+
+/// What `validate` and `repair` need from a graph's storage, so `Graph`
+/// and `WeightedGraph` share one implementation of each.
+pub(crate) trait Storage<N> {
+    fn is_directed(&self) -> bool;
+    fn policy(&self) -> EdgePolicy;
+    fn has_list(&self, node: &N) -> bool;
+    /// Endpoints of every edge in the edge table, in any order.
+    fn edge_endpoints<'a>(&'a self) -> impl Iterator<Item = (EdgeId, &'a N, &'a N)>
+    where
+        N: 'a;
+    /// Edge id of every adjacency entry, mirror entries included.
+    fn stored_ids(&self) -> impl Iterator<Item = EdgeId>;
+    fn add_node(&mut self, node: N) -> bool;
+    /// Stores the edge under both endpoints again, recording it as removed
+    /// and re-added under the same id.
+    fn restore_edge(&mut self, id: EdgeId);
+    fn remove_edge(&mut self, id: EdgeId);
+    /// Rebuilds the incoming-edge index if it is enabled.
+    fn rebuild_reverse_index(&mut self);
+}
+
+/// Shared body of `validate`. Violations are reported in edge id order.
+pub(crate) fn validate<N: Eq + Hash + Clone, G: Storage<N>>(graph: &G) -> Result<(), Vec<Violation<N>>> {
+    let mut stored: HashMap<EdgeId, usize> = HashMap::new();
+    for id in graph.stored_ids() {
+        *stored.entry(id).or_default() += 1;
+    }
+    let mut edges: Vec<_> = graph.edge_endpoints().collect();
+    edges.sort_by_key(|(id, _, _)| *id);
+
+    let is_directed = graph.is_directed();
+    let mut seen: HashSet<(&N, &N)> = HashSet::new();
+    let mut violations = Vec::new();
+    for (id, u, v) in edges {
+        let (source, target) = (u.clone(), v.clone());
+        if !graph.has_list(v) {
+            violations.push(Violation::DanglingTarget { source: source.clone(), target: target.clone(), id });
+        }
+        if !is_directed && stored.get(&id) != Some(&2) {
+            violations.push(Violation::Asymmetric { source: source.clone(), target: target.clone(), id });
+        }
+        let parallel = !seen.insert((u, v)) || (!is_directed && seen.contains(&(v, u)) && u != v);
+        if parallel && !graph.policy().allow_parallel {
+            violations.push(Violation::DuplicateEdge { source, target, id });
+        }
+    }
+    if violations.is_empty() { Ok(()) } else { Err(violations) }
+}
+
+/// Shared body of `repair`. Every fix goes through the graph's own
+/// mutators, so it bumps the version and shows up in the change log.
+pub(crate) fn repair<N: Eq + Hash + Clone, G: Storage<N>>(graph: &mut G) -> Vec<Violation<N>> {
+    let Err(violations) = validate(graph) else {
+        return Vec::new();
+    };
+    for violation in &violations {
+        match violation {
+            Violation::DanglingTarget { target, .. } => {
+                graph.add_node(target.clone());
+            }
+            Violation::Asymmetric { id, .. } => graph.restore_edge(*id),
+            Violation::DuplicateEdge { .. } => {}
+        }
+    }
+    for violation in &violations {
+        if let Violation::DuplicateEdge { id, .. } = violation {
+            graph.remove_edge(*id);
+        }
+    }
+    graph.rebuild_reverse_index();
+    violations
+}
+
+/// Assigns edge ids to the entries of an unchecked adjacency map. In an
+/// undirected graph a `u -> v` entry reuses the id of a `v -> u` entry
+/// still waiting for its mirror if `pairs` accepts the two; otherwise
+/// `new_edge` allocates an id for it.
+pub(crate) fn assign_ids<N, T>(
+    is_directed: bool,
+    adjacency: HashMap<N, Vec<T>>,
+    target: impl Fn(&T) -> &N,
+    pairs: impl Fn(&T, &T) -> bool,
+    mut new_edge: impl FnMut(&N, &T) -> EdgeId,
+) -> Vec<(N, Vec<(T, EdgeId)>)>
+where
+    N: Eq + Hash + Clone,
+    T: Clone,
+{
+    // undirected entries still waiting for their mirror, keyed by (u, v)
+    let mut unpaired: HashMap<(N, N), Vec<(EdgeId, T)>> = HashMap::new();
+    let mut lists = Vec::with_capacity(adjacency.len());
+    for (u, entries) in adjacency {
+        let mut out = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let v = target(&entry);
+            let mirror = unpaired.get_mut(&(v.clone(), u.clone())).and_then(|waiting| {
+                let i = waiting.iter().position(|(_, x)| pairs(x, &entry))?;
+                Some(waiting.swap_remove(i).0)
+            });
+            let id = match mirror {
+                Some(id) => id,
+                None => {
+                    let id = new_edge(&u, &entry);
+                    if !is_directed {
+                        unpaired.entry((u.clone(), v.clone())).or_default().push((id, entry.clone()));
+                    }
+                    id
+                }
+            };
+            out.push((entry, id));
+        }
+        lists.push((u, out));
+    }
+    lists
+}
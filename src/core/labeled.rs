@@ -0,0 +1,297 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::algos::bfs::BfsExt;
+use crate::algos::dfs::DfsExt;
+use crate::core::edge::{EdgeId, EdgeRef};
+use crate::core::error::GraphError;
+use crate::core::graph::Graph;
+use crate::core::policy::EdgePolicy;
+use crate::core::traits::GraphBase;
+
+/// Maps labels to dense indices `0..len()` in the order they were first
+/// seen, and back.
+#[derive(Debug, Clone)]
+pub struct Interner<L> {
+    labels: Vec<L>,
+    index: HashMap<L, usize>,
+}
+
+impl<L: Eq + Hash + Clone> Interner<L> {
+    pub fn new() -> Self {
+        Interner { labels: Vec::new(), index: HashMap::new() }
+    }
+
+    /// Returns the index of `label`, assigning the next free one if it is
+    /// new.
+    pub fn intern<Q>(&mut self, label: &Q) -> usize
+    where
+        L: Borrow<Q>,
+        Q: Eq + Hash + ToOwned<Owned = L> + ?Sized,
+    {
+        if let Some(&i) = self.index.get(label) {
+            return i;
+        }
+        let i = self.labels.len();
+        self.labels.push(label.to_owned());
+        self.index.insert(label.to_owned(), i);
+        i
+    }
+
+    pub fn get<Q>(&self, label: &Q) -> Option<usize>
+    where
+        L: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.index.get(label).copied()
+    }
+
+    pub fn resolve(&self, index: usize) -> Option<&L> {
+        self.labels.get(index)
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+
+    /// Iterates over the labels in index order.
+    pub fn labels(&self) -> impl Iterator<Item = &L> {
+        self.labels.iter()
+    }
+}
+
+impl<L: Eq + Hash + Clone> Default for Interner<L> {
+    fn default() -> Self {
+        Interner::new()
+    }
+}
+
+/// A `Graph<usize>` whose nodes are addressed by label.
+///
+/// Every label is interned to a dense index that is also its node in the
+/// underlying graph, so traversals run on plain integers and the visited
+/// set is a flat vector. The `*_by_label` methods translate in both
+/// directions.
+#[derive(Debug, Clone)]
+pub struct LabeledGraph<L = String> {
+    graph: Graph<usize>,
+    interner: Interner<L>,
+}
+
+impl<L: Eq + Hash + Clone> LabeledGraph<L> {
+    pub fn new(is_directed: bool) -> Self {
+        LabeledGraph { graph: Graph::new(is_directed), interner: Interner::new() }
+    }
+
+    /// Creates an empty graph whose `add_edge_by_label` enforces `policy`.
+    pub fn with_policy(is_directed: bool, policy: EdgePolicy) -> Self {
+        LabeledGraph { graph: Graph::with_policy(is_directed, policy), interner: Interner::new() }
+    }
+
+    /// The underlying graph, with nodes numbered by interned index.
+    pub fn graph(&self) -> &Graph<usize> {
+        &self.graph
+    }
+
+    pub fn interner(&self) -> &Interner<L> {
+        &self.interner
+    }
+
+    pub fn index_of<Q>(&self, label: &Q) -> Option<usize>
+    where
+        L: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.interner.get(label)
+    }
+
+    pub fn label(&self, index: usize) -> Option<&L> {
+        self.interner.resolve(index)
+    }
+
+    /// Adds a node for `label` if it has none yet, returning its index.
+    pub fn add_node_by_label<Q>(&mut self, label: &Q) -> usize
+    where
+        L: Borrow<Q>,
+        Q: Eq + Hash + ToOwned<Owned = L> + ?Sized,
+    {
+        let i = self.interner.intern(label);
+        self.graph.add_node(i);
+        i
+    }
+
+    /// Adds the edge `u -> v`, interning labels that are new.
+    pub fn add_edge_by_label<Q>(&mut self, u: &Q, v: &Q) -> Result<EdgeId, GraphError>
+    where
+        L: Borrow<Q>,
+        Q: Eq + Hash + ToOwned<Owned = L> + ?Sized,
+    {
+        let u = self.add_node_by_label(u);
+        let v = self.add_node_by_label(v);
+        self.graph.add_edge(u, v)
+    }
+
+    pub fn has_edge_by_label<Q>(&self, u: &Q, v: &Q) -> bool
+    where
+        L: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        match (self.index_of(u), self.index_of(v)) {
+            (Some(u), Some(v)) => self.graph.has_edge(&u, &v),
+            _ => false,
+        }
+    }
+
+    /// Index of `label`, or `NodeNotFound` if it was never interned.
+    fn require<Q>(&self, label: &Q) -> Result<usize, GraphError>
+    where
+        L: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.index_of(label).ok_or(GraphError::NodeNotFound)
+    }
+
+    /// `bfs` from `start`, reported as labels.
+    pub fn bfs_by_label<Q>(&self, start: &Q) -> Result<Vec<&L>, GraphError>
+    where
+        L: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let order = self.bfs(self.require(start)?)?;
+        self.to_labels(order)
+    }
+
+    /// `dfs` from `start`, reported as labels.
+    pub fn dfs_by_label<Q>(&self, start: &Q) -> Result<Vec<&L>, GraphError>
+    where
+        L: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let order = self.dfs(self.require(start)?)?;
+        self.to_labels(order)
+    }
+
+    /// `shortest_path_bfs` between two labels, or `Ok(None)` if there is no
+    /// path.
+    pub fn shortest_path_by_label<Q>(&self, start: &Q, end: &Q) -> Result<Option<Vec<&L>>, GraphError>
+    where
+        L: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let path = self.shortest_path_bfs(self.require(start)?, self.require(end)?)?;
+        path.map(|p| self.to_labels(p)).transpose()
+    }
+
+    /// Translates indices produced by a traversal back to labels. Fails
+    /// with `NodeNotFound` on an index the interner never issued.
+    pub fn to_labels<I: IntoIterator<Item = usize>>(&self, indices: I) -> Result<Vec<&L>, GraphError> {
+        indices.into_iter().map(|i| self.interner.resolve(i).ok_or(GraphError::NodeNotFound)).collect()
+    }
+}
+
+impl<L: Eq + Hash + Clone> GraphBase for LabeledGraph<L> {
+    type Node = usize;
+    type Weight = ();
+
+    fn is_directed(&self) -> bool {
+        self.graph.is_directed()
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = usize> + '_ {
+        0..self.interner.len()
+    }
+
+    fn neighbors<'a>(&'a self, node: &usize) -> impl Iterator<Item = usize> + use<'a, L> {
+        GraphBase::neighbors(&self.graph, node)
+    }
+
+    fn out_edges<'a>(&'a self, node: &usize) -> impl Iterator<Item = EdgeRef<usize>> + use<'a, L> {
+        GraphBase::out_edges(&self.graph, node)
+    }
+
+    fn in_edges<'a>(&'a self, node: &usize) -> impl Iterator<Item = EdgeRef<usize>> + use<'a, L> {
+        GraphBase::in_edges(&self.graph, node)
+    }
+
+    fn edges(&self) -> impl Iterator<Item = EdgeRef<usize>> + '_ {
+        GraphBase::edges(&self.graph)
+    }
+
+    fn contains_node(&self, node: &usize) -> bool {
+        *node < self.interner.len()
+    }
+
+    fn node_count(&self) -> usize {
+        self.interner.len()
+    }
+
+    fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    fn node_index(&self, node: &usize) -> Option<usize> {
+        self.contains_node(node).then_some(*node)
+    }
+
+    fn node_bound(&self) -> Option<usize> {
+        Some(self.interner.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_interner_is_dense_and_stable() {
+        let mut interner: Interner<String> = Interner::new();
+        assert_eq!(interner.intern("auth-svc"), 0);
+        assert_eq!(interner.intern("db-primary"), 1);
+        assert_eq!(interner.intern("auth-svc"), 0);
+        assert_eq!(interner.get("db-primary"), Some(1));
+        assert_eq!(interner.get("cache"), None);
+        assert_eq!(interner.resolve(1).map(String::as_str), Some("db-primary"));
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn test_traversals_by_label() {
+        let mut graph: LabeledGraph = LabeledGraph::new(true);
+        graph.add_edge_by_label("gateway", "auth-svc").unwrap();
+        graph.add_edge_by_label("gateway", "orders").unwrap();
+        graph.add_edge_by_label("auth-svc", "db-primary").unwrap();
+        graph.add_edge_by_label("orders", "db-primary").unwrap();
+        graph.add_node_by_label("batch");
+
+        assert_eq!(graph.bfs_by_label("gateway").unwrap(), ["gateway", "auth-svc", "orders", "db-primary"]);
+        assert_eq!(graph.dfs_by_label("gateway").unwrap(), ["gateway", "auth-svc", "db-primary", "orders"]);
+        assert_eq!(graph.shortest_path_by_label("orders", "db-primary").unwrap().unwrap(), ["orders", "db-primary"]);
+        assert_eq!(graph.shortest_path_by_label("db-primary", "gateway").unwrap(), None);
+        assert_eq!(graph.bfs_by_label("batch").unwrap(), ["batch"]);
+        assert_eq!(graph.bfs_by_label("unknown"), Err(GraphError::NodeNotFound));
+        assert_eq!(graph.shortest_path_by_label("gateway", "unknown"), Err(GraphError::NodeNotFound));
+        assert!(graph.has_edge_by_label("gateway", "orders"));
+        assert!(!graph.has_edge_by_label("orders", "gateway"));
+    }
+
+    #[test]
+    fn test_labeled_graph_uses_dense_indices() {
+        let mut graph: LabeledGraph = LabeledGraph::new(false);
+        graph.add_edge_by_label("a", "b").unwrap();
+        graph.add_edge_by_label("b", "c").unwrap();
+
+        assert_eq!(graph.node_bound(), Some(3));
+        assert_eq!(graph.node_index(&2), Some(2));
+        assert_eq!(graph.node_index(&3), None);
+        let c = graph.index_of("c").unwrap();
+        assert_eq!(graph.bfs_distances(c).unwrap()[&graph.index_of("a").unwrap()], 2);
+        assert_eq!(graph.to_labels(graph.bfs(c).unwrap()).unwrap(), ["c", "b", "a"]);
+        assert_eq!(graph.to_labels([c, 3]), Err(GraphError::NodeNotFound));
+        assert_eq!(graph.graph().node_count(), 3);
+    }
+}
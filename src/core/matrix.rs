@@ -0,0 +1,524 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+
+use crate::core::edge::{EdgeId, EdgeRef};
+use crate::core::error::GraphError;
+use crate::core::graph::Graph;
+use crate::core::policy::EdgePolicy;
+use crate::core::traits::GraphBase;
+use crate::core::weight::Weight;
+use crate::core::weightedgraph::WeightedGraph;
+
+/// Dense node numbering shared by the matrix backends.
+#[derive(Debug, Clone)]
+struct NodeTable<N> {
+    nodes: Vec<N>,
+    index: HashMap<N, usize>,
+}
+
+impl<N: Eq + Hash + Clone> NodeTable<N> {
+    fn new() -> Self {
+        NodeTable { nodes: Vec::new(), index: HashMap::new() }
+    }
+
+    /// Id of the matrix cell `u -> v`. Undirected edges use the upper
+    /// triangle so both directions share one id. Ids depend on the node
+    /// count and change whenever a node is added.
+    fn edge_id(&self, u: usize, v: usize, is_directed: bool) -> EdgeId {
+        let (a, b) = if is_directed || u <= v { (u, v) } else { (v, u) };
+        EdgeId(a * self.nodes.len() + b)
+    }
+
+    /// Returns the index of `node` and whether it was newly added.
+    fn intern(&mut self, node: N) -> (usize, bool) {
+        if let Some(&i) = self.index.get(&node) {
+            return (i, false);
+        }
+        let i = self.nodes.len();
+        self.index.insert(node.clone(), i);
+        self.nodes.push(node);
+        (i, true)
+    }
+}
+
+/// Unweighted graph stored as an `n x n` bit matrix.
+///
+/// Edge-existence checks are O(1) and each node costs `n` bits. Parallel
+/// edges collapse into one bit. Neighbors are reported in node insertion
+/// order. Adding a node re-lays the matrix out, so insert all nodes up
+/// front (e.g. with `with_nodes`) when building large graphs. Edge ids are
+/// derived from the cell position and are only stable while the node set
+/// is unchanged.
+#[derive(Debug, Clone)]
+pub struct MatrixGraph<N = i32> {
+    table: NodeTable<N>,
+    words_per_row: usize,
+    bits: Vec<u64>,
+    is_directed: bool,
+    policy: EdgePolicy,
+}
+
+impl<N: Eq + Hash + Clone> MatrixGraph<N> {
+    pub fn new(is_directed: bool) -> Self {
+        MatrixGraph::with_policy(is_directed, EdgePolicy::default())
+    }
+
+    /// Creates an empty graph whose `add_edge` enforces `policy`. When the
+    /// policy allows parallel edges they still collapse into one bit.
+    pub fn with_policy(is_directed: bool, policy: EdgePolicy) -> Self {
+        MatrixGraph {
+            table: NodeTable::new(),
+            words_per_row: 0,
+            bits: Vec::new(),
+            is_directed,
+            policy,
+        }
+    }
+
+    pub fn policy(&self) -> EdgePolicy {
+        self.policy
+    }
+
+    /// Creates an edgeless graph over `nodes`, sizing the matrix once.
+    pub fn with_nodes<I: IntoIterator<Item = N>>(is_directed: bool, nodes: I) -> Self {
+        let mut table = NodeTable::new();
+        for node in nodes {
+            table.intern(node);
+        }
+        let words_per_row = table.nodes.len().div_ceil(64);
+        MatrixGraph {
+            bits: vec![0; words_per_row * table.nodes.len()],
+            table,
+            words_per_row,
+            is_directed,
+            policy: EdgePolicy::default(),
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.table.nodes.len()
+    }
+
+    /// Adds `node` if missing and returns its row index.
+    pub fn add_node(&mut self, node: N) -> usize {
+        let (i, added) = self.table.intern(node);
+        if added {
+            let n = self.table.nodes.len();
+            let words = n.div_ceil(64);
+            let mut bits = vec![0; words * n];
+            for row in 0..n - 1 {
+                let old = &self.bits[row * self.words_per_row..(row + 1) * self.words_per_row];
+                bits[row * words..row * words + old.len()].copy_from_slice(old);
+            }
+            self.bits = bits;
+            self.words_per_row = words;
+        }
+        i
+    }
+
+    /// Adds the edge `u -> v`, creating missing nodes. Fails if the policy
+    /// rejects it.
+    pub fn add_edge(&mut self, u: N, v: N) -> Result<(), GraphError> {
+        self.policy.check(&u, &v, || self.has_edge(&u, &v))?;
+        self.insert_edge(u, v);
+        Ok(())
+    }
+
+    /// `add_edge` for edges the policy is known to accept.
+    fn insert_edge(&mut self, u: N, v: N) {
+        let ui = self.add_node(u);
+        let vi = self.add_node(v);
+        self.set(ui, vi, true);
+        if !self.is_directed {
+            self.set(vi, ui, true);
+        }
+    }
+
+    /// Removes the edge `u -> v` (both directions if undirected).
+    pub fn remove_edge(&mut self, u: &N, v: &N) -> Result<(), GraphError> {
+        let (Some(&ui), Some(&vi)) = (self.table.index.get(u), self.table.index.get(v)) else {
+            return Err(GraphError::NodeNotFound);
+        };
+        if !self.get(ui, vi) {
+            return Err(GraphError::EdgeNotFound);
+        }
+        self.set(ui, vi, false);
+        if !self.is_directed {
+            self.set(vi, ui, false);
+        }
+        Ok(())
+    }
+
+    /// O(1) check for the edge `u -> v`.
+    pub fn has_edge(&self, u: &N, v: &N) -> bool {
+        match (self.table.index.get(u), self.table.index.get(v)) {
+            (Some(&ui), Some(&vi)) => self.get(ui, vi),
+            _ => false,
+        }
+    }
+
+    fn get(&self, u: usize, v: usize) -> bool {
+        self.bits[u * self.words_per_row + v / 64] & (1 << (v % 64)) != 0
+    }
+
+    fn set(&mut self, u: usize, v: usize, on: bool) {
+        let word = &mut self.bits[u * self.words_per_row + v / 64];
+        if on {
+            *word |= 1 << (v % 64);
+        } else {
+            *word &= !(1 << (v % 64));
+        }
+    }
+
+    fn row_indices(&self, u: usize) -> impl Iterator<Item = usize> + '_ {
+        let row = &self.bits[u * self.words_per_row..(u + 1) * self.words_per_row];
+        row.iter().enumerate().flat_map(|(w, &word)| {
+            (0..64).filter(move |b| word & (1 << b) != 0).map(move |b| w * 64 + b)
+        })
+    }
+}
+
+impl<N: Eq + Hash + Clone> GraphBase for MatrixGraph<N> {
+    type Node = N;
+    type Weight = ();
+
+    fn is_directed(&self) -> bool {
+        self.is_directed
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = N> + '_ {
+        self.table.nodes.iter().cloned()
+    }
+
+    fn neighbors<'a>(&'a self, node: &N) -> impl Iterator<Item = N> + use<'a, N> {
+        let row = self.table.index.get(node).copied();
+        row.into_iter()
+            .flat_map(|u| self.row_indices(u))
+            .map(|v| self.table.nodes[v].clone())
+    }
+
+    fn out_edges<'a>(&'a self, node: &N) -> impl Iterator<Item = EdgeRef<N>> + use<'a, N> {
+        let row = self.table.index.get(node).copied();
+        row.into_iter()
+            .flat_map(|u| self.row_indices(u).map(move |v| (u, v)))
+            .map(|(u, v)| EdgeRef {
+                source: self.table.nodes[u].clone(),
+                target: self.table.nodes[v].clone(),
+                weight: (),
+                id: self.table.edge_id(u, v, self.is_directed),
+            })
+    }
+
+    fn contains_node(&self, node: &N) -> bool {
+        self.table.index.contains_key(node)
+    }
+
+    fn node_index(&self, node: &N) -> Option<usize> {
+        self.table.index.get(node).copied()
+    }
+
+    fn node_bound(&self) -> Option<usize> {
+        Some(self.table.nodes.len())
+    }
+}
+
+/// Structure only: the matrix has no room for node or edge payloads, so
+/// they are not carried over, and a conversion back yields a `Graph` without
+/// any.
+impl<N: Eq + Hash + Clone, NData, EData> From<&Graph<N, NData, EData>> for MatrixGraph<N> {
+    fn from(graph: &Graph<N, NData, EData>) -> Self {
+        let mut matrix = MatrixGraph::with_nodes(graph.is_directed(), graph.nodes().cloned());
+        matrix.policy = graph.policy();
+        for e in graph.edges() {
+            matrix.insert_edge(e.source.clone(), e.target.clone());
+        }
+        matrix
+    }
+}
+
+impl<N: Eq + Hash + Clone> From<&MatrixGraph<N>> for Graph<N> {
+    fn from(matrix: &MatrixGraph<N>) -> Self {
+        let mut graph = Graph::new(matrix.is_directed);
+        for node in matrix.nodes() {
+            graph.add_node(node);
+        }
+        for e in matrix.edges() {
+            graph.insert_edge(e.source, e.target);
+        }
+        graph
+    }
+}
+
+/// Weighted graph stored as an `n x n` matrix of optional weights.
+///
+/// Like `MatrixGraph` this holds at most one edge per ordered pair, and its
+/// edge ids follow the same cell-based scheme. When converting from a
+/// `WeightedGraph` with parallel edges the lightest one is kept.
+#[derive(Debug, Clone)]
+pub struct WeightedMatrixGraph<N = i32, W = i32> {
+    table: NodeTable<N>,
+    values: Vec<Option<W>>,
+    is_directed: bool,
+}
+
+impl<N: Eq + Hash + Clone, W: Weight> WeightedMatrixGraph<N, W> {
+    pub fn new(is_directed: bool) -> Self {
+        WeightedMatrixGraph {
+            table: NodeTable::new(),
+            values: Vec::new(),
+            is_directed,
+        }
+    }
+
+    /// Creates an edgeless graph over `nodes`, sizing the matrix once.
+    pub fn with_nodes<I: IntoIterator<Item = N>>(is_directed: bool, nodes: I) -> Self {
+        let mut table = NodeTable::new();
+        for node in nodes {
+            table.intern(node);
+        }
+        let n = table.nodes.len();
+        WeightedMatrixGraph {
+            table,
+            values: vec![None; n * n],
+            is_directed,
+        }
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.table.nodes.len()
+    }
+
+    /// Adds `node` if missing and returns its row index.
+    pub fn add_node(&mut self, node: N) -> usize {
+        let (i, added) = self.table.intern(node);
+        if added {
+            let n = self.table.nodes.len();
+            let mut values = vec![None; n * n];
+            for row in 0..n - 1 {
+                let old = &self.values[row * (n - 1)..(row + 1) * (n - 1)];
+                values[row * n..row * n + n - 1].copy_from_slice(old);
+            }
+            self.values = values;
+        }
+        i
+    }
+
+    /// Sets the weight of `u -> v`, replacing any previous weight. Fails if
+    /// the weight is invalid.
+    pub fn add_edge(&mut self, u: N, v: N, weight: W) -> Result<(), GraphError> {
+        if !weight.is_valid() {
+            return Err(GraphError::InvalidWeight);
+        }
+        self.insert_edge(u, v, weight);
+        Ok(())
+    }
+
+    /// `add_edge` for weights that are already known to be valid.
+    fn insert_edge(&mut self, u: N, v: N, weight: W) {
+        let ui = self.add_node(u);
+        let vi = self.add_node(v);
+        let n = self.table.nodes.len();
+        self.values[ui * n + vi] = Some(weight);
+        if !self.is_directed {
+            self.values[vi * n + ui] = Some(weight);
+        }
+    }
+
+    /// Removes the edge `u -> v` (both directions if undirected), returning
+    /// its weight.
+    pub fn remove_edge(&mut self, u: &N, v: &N) -> Result<W, GraphError> {
+        let (Some(&ui), Some(&vi)) = (self.table.index.get(u), self.table.index.get(v)) else {
+            return Err(GraphError::NodeNotFound);
+        };
+        let n = self.table.nodes.len();
+        let weight = self.values[ui * n + vi].take().ok_or(GraphError::EdgeNotFound)?;
+        if !self.is_directed {
+            self.values[vi * n + ui] = None;
+        }
+        Ok(weight)
+    }
+
+    /// O(1) check for the edge `u -> v`.
+    pub fn has_edge(&self, u: &N, v: &N) -> bool {
+        self.weight(u, v).is_some()
+    }
+
+    /// Weight of the edge `u -> v`, if present.
+    pub fn weight(&self, u: &N, v: &N) -> Option<W> {
+        let ui = *self.table.index.get(u)?;
+        let vi = *self.table.index.get(v)?;
+        self.values[ui * self.table.nodes.len() + vi]
+    }
+
+    fn row(&self, u: usize) -> impl Iterator<Item = (usize, W)> + '_ {
+        let n = self.table.nodes.len();
+        self.values[u * n..(u + 1) * n]
+            .iter()
+            .enumerate()
+            .filter_map(|(v, w)| w.map(|w| (v, w)))
+    }
+}
+
+impl<N: Eq + Hash + Clone, W: Weight> GraphBase for WeightedMatrixGraph<N, W> {
+    type Node = N;
+    type Weight = W;
+
+    fn is_directed(&self) -> bool {
+        self.is_directed
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = N> + '_ {
+        self.table.nodes.iter().cloned()
+    }
+
+    fn neighbors<'a>(&'a self, node: &N) -> impl Iterator<Item = N> + use<'a, N, W> {
+        let row = self.table.index.get(node).copied();
+        row.into_iter()
+            .flat_map(|u| self.row(u))
+            .map(|(v, _)| self.table.nodes[v].clone())
+    }
+
+    fn out_edges<'a>(&'a self, node: &N) -> impl Iterator<Item = EdgeRef<N, W>> + use<'a, N, W> {
+        let row = self.table.index.get(node).copied();
+        row.into_iter()
+            .flat_map(|u| self.row(u).map(move |(v, w)| (u, v, w)))
+            .map(|(u, v, w)| EdgeRef {
+                source: self.table.nodes[u].clone(),
+                target: self.table.nodes[v].clone(),
+                weight: w,
+                id: self.table.edge_id(u, v, self.is_directed),
+            })
+    }
+
+    fn contains_node(&self, node: &N) -> bool {
+        self.table.index.contains_key(node)
+    }
+
+    fn node_index(&self, node: &N) -> Option<usize> {
+        self.table.index.get(node).copied()
+    }
+
+    fn node_bound(&self) -> Option<usize> {
+        Some(self.table.nodes.len())
+    }
+}
+
+impl<N: Eq + Hash + Clone, W: Weight> From<&WeightedGraph<N, W>> for WeightedMatrixGraph<N, W> {
+    fn from(graph: &WeightedGraph<N, W>) -> Self {
+        let mut matrix = WeightedMatrixGraph::with_nodes(graph.is_directed(), graph.nodes().cloned());
+        for e in graph.edges() {
+            let keep = match matrix.weight(e.source, e.target) {
+                Some(old) => e.weight < old,
+                None => true,
+            };
+            if keep {
+                matrix.insert_edge(e.source.clone(), e.target.clone(), e.weight);
+            }
+        }
+        matrix
+    }
+}
+
+impl<N: Eq + Hash + Clone, W: Weight> From<&WeightedMatrixGraph<N, W>> for WeightedGraph<N, W> {
+    fn from(matrix: &WeightedMatrixGraph<N, W>) -> Self {
+        let mut graph = WeightedGraph::new(matrix.is_directed);
+        for (u, node) in matrix.table.nodes.iter().enumerate() {
+            graph.add_node(node.clone());
+            for (v, w) in matrix.row(u) {
+                // undirected edges are stored in both triangles; add each once
+                if matrix.is_directed || v >= u {
+                    graph.insert_edge(node.clone(), matrix.table.nodes[v].clone(), w);
+                }
+            }
+        }
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algos::bfs::BfsExt;
+    use crate::algos::dfs::DfsExt;
+
+    #[test]
+    fn test_matrix_has_edge() {
+        let mut graph = Graph::new(false);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(2, 3).unwrap();
+        graph.add_edge(3, 3).unwrap();
+        let matrix = MatrixGraph::from(&graph);
+
+        assert!(matrix.has_edge(&1, &2));
+        assert!(matrix.has_edge(&2, &1));
+        assert!(matrix.has_edge(&3, &3));
+        assert!(!matrix.has_edge(&1, &3));
+        assert!(!matrix.has_edge(&1, &99));
+        // both directions of an undirected edge share one id
+        let forward = matrix.out_edges(&1).next().unwrap();
+        let back = matrix.out_edges(&2).find(|e| e.target == 1).unwrap();
+        assert_eq!(forward.id, back.id);
+        assert_eq!(matrix.edge_count(), 3);
+    }
+
+    #[test]
+    fn test_matrix_traversals() {
+        let mut matrix = MatrixGraph::new(true);
+        // more than 64 nodes so rows span several words
+        for i in 0..100 {
+            matrix.add_edge(i, i + 1).unwrap();
+        }
+        matrix.add_edge(0, 50).unwrap();
+
+        assert_eq!(matrix.node_count(), 101);
+        assert_eq!(matrix.bfs(0).unwrap().len(), 101);
+        assert_eq!(matrix.shortest_path_bfs(0, 52).unwrap(), Some(vec![0, 50, 51, 52]));
+        assert_eq!(matrix.dfs(98).unwrap(), vec![98, 99, 100]);
+        assert!(matrix.remove_edge(&0, &50).is_ok());
+        assert!(!matrix.has_edge(&0, &50));
+        assert_eq!(matrix.remove_edge(&0, &50), Err(GraphError::EdgeNotFound));
+        assert_eq!(matrix.remove_edge(&0, &500), Err(GraphError::NodeNotFound));
+        assert_eq!(matrix.bfs_distances(0).unwrap()[&52], 52);
+
+        let mut simple = MatrixGraph::with_policy(false, EdgePolicy::SIMPLE);
+        assert_eq!(simple.add_edge(1, 2), Ok(()));
+        assert_eq!(simple.add_edge(2, 1), Err(GraphError::DuplicateEdge));
+        assert_eq!(simple.add_edge(3, 3), Err(GraphError::SelfLoop));
+        assert!(!simple.contains_node(&3));
+        assert_eq!(matrix.add_edge(0, 1), Ok(()));
+    }
+
+    #[test]
+    fn test_matrix_round_trip() {
+        let mut graph: Graph<&str, u8, u8> = Graph::attributed(false, Default::default());
+        graph.add_edge_with_data("a", "b", 1).unwrap();
+        graph.add_edge("b", "c").unwrap();
+        graph.add_node_with_data("lonely", 2);
+
+        // structure only: the payloads don't survive the trip
+        let back = Graph::from(&MatrixGraph::from(&graph));
+        assert_eq!(back.node_count(), 4);
+        assert_eq!(back.node_data(&"lonely"), None);
+        assert!(back.edges().all(|e| back.edge_data(e.id).is_none()));
+        let mut edges: Vec<_> = back.edges().map(|e| if e.source < e.target { (*e.source, *e.target) } else { (*e.target, *e.source) }).collect();
+        edges.sort();
+        assert_eq!(edges, vec![("a", "b"), ("b", "c")]);
+    }
+
+    #[test]
+    fn test_weighted_matrix() {
+        let mut graph: WeightedGraph<i32, f64> = WeightedGraph::new(false);
+        graph.add_edge(1, 2, 3.0).unwrap();
+        graph.add_edge(1, 2, 1.5).unwrap();
+        graph.add_edge(2, 3, 2.0).unwrap();
+        let matrix = WeightedMatrixGraph::from(&graph);
+
+        assert_eq!(matrix.weight(&2, &1), Some(1.5));
+        assert_eq!(matrix.weight(&1, &3), None);
+        assert_eq!(matrix.bfs(1).unwrap(), vec![1, 2, 3]);
+
+        let back = WeightedGraph::from(&matrix);
+        assert_eq!(back.path_weight(&[1, 2, 3]), Ok(3.5));
+        assert_eq!(back.degree(&2), Ok(2));
+    }
+}
@@ -1,2 +1,17 @@
 pub  mod graph;
 pub mod weightedgraph;
+pub mod weight;
+pub mod traits;
+pub mod csr;
+pub mod matrix;
+pub mod views;
+pub mod error;
+pub mod policy;
+pub mod edge;
+pub mod labeled;
+pub mod changelog;
+pub mod transaction;
+pub mod snapshot;
+pub(crate) mod persistent;
+pub(crate) mod integrity;
+pub mod temporal;
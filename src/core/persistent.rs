@@ -0,0 +1,309 @@
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::sync::Arc;
+
+/// Children per branch; each level of the trie consumes this many bits'
+/// worth of the key's hash.
+const BITS: u32 = 5;
+const WIDTH: usize = 1 << BITS;
+/// Entries a leaf holds before it is split into a branch.
+const LEAF_CAP: usize = 8;
+/// Deepest level that still has hash bits left to split on. Leaves here
+/// grow without bound, which only happens on full 64-bit collisions.
+const MAX_DEPTH: u32 = u64::BITS / BITS;
+
+/// Persistent hash map: a hash trie whose nodes sit behind `Arc`s.
+///
+/// Cloning it is O(1). A write copies only the nodes on the path to the key,
+/// O(log n) of them with at most `WIDTH` pointers each, so a map and its
+/// clones share everything else. Iteration order depends on the hashes and
+/// is the same for a map and its clones.
+pub(crate) struct PersistentMap<K, V> {
+    root: Arc<TrieNode<K, V>>,
+    len: usize,
+    hasher: RandomState,
+}
+
+#[derive(Clone)]
+enum TrieNode<K, V> {
+    /// Entries with their full hash, so splitting never rehashes.
+    Leaf(Vec<(u64, K, V)>),
+    /// Exactly `WIDTH` slots, indexed by the next `BITS` of the hash.
+    Branch(Vec<Option<Arc<TrieNode<K, V>>>>),
+}
+
+impl<K, V> Clone for PersistentMap<K, V> {
+    fn clone(&self) -> Self {
+        PersistentMap { root: Arc::clone(&self.root), len: self.len, hasher: self.hasher.clone() }
+    }
+}
+
+impl<K, V> Default for PersistentMap<K, V> {
+    fn default() -> Self {
+        PersistentMap { root: Arc::new(TrieNode::Leaf(Vec::new())), len: 0, hasher: RandomState::new() }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for PersistentMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+fn slot(hash: u64, depth: u32) -> usize {
+    ((hash >> (depth * BITS)) as usize) & (WIDTH - 1)
+}
+
+impl<K, V> PersistentMap<K, V> {
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let mut stack = vec![self.root.as_ref()];
+        let mut leaf: std::slice::Iter<'_, (u64, K, V)> = [].iter();
+        std::iter::from_fn(move || loop {
+            if let Some((_, k, v)) = leaf.next() {
+                return Some((k, v));
+            }
+            match stack.pop()? {
+                TrieNode::Leaf(entries) => leaf = entries.iter(),
+                TrieNode::Branch(children) => stack.extend(children.iter().rev().flatten().map(Arc::as_ref)),
+            }
+        })
+    }
+
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(k, _)| k)
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, v)| v)
+    }
+
+    /// Whether both maps still share their root, i.e. neither was written
+    /// since one was cloned from the other.
+    #[cfg(test)]
+    pub(crate) fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.root, &other.root)
+    }
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> PersistentMap<K, V> {
+    fn hash(&self, key: &K) -> u64 {
+        self.hasher.hash_one(key)
+    }
+
+    pub(crate) fn get_key_value(&self, key: &K) -> Option<(&K, &V)> {
+        self.find(self.hash(key), key)
+    }
+
+    pub(crate) fn get(&self, key: &K) -> Option<&V> {
+        self.get_key_value(key).map(|(_, v)| v)
+    }
+
+    pub(crate) fn contains_key(&self, key: &K) -> bool {
+        self.get_key_value(key).is_some()
+    }
+
+    /// The value of `key` for writing, copying the path to it. A missing key
+    /// copies nothing.
+    pub(crate) fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let hash = self.hash(key);
+        self.find(hash, key)?;
+        let entries = leaf_mut(&mut self.root, hash);
+        entries.iter_mut().find(|(h, k, _)| *h == hash && k == key).map(|(_, _, v)| v)
+    }
+
+    /// The value of `key` for writing, inserting `default()` first if it is
+    /// missing, in a single walk down the trie. Also returns whether the key
+    /// was inserted.
+    pub(crate) fn entry_or_insert_with(&mut self, key: K, default: impl FnOnce() -> V) -> (&mut V, bool) {
+        let hash = self.hash(&key);
+        let entries = leaf_with_room(&mut self.root, hash);
+        match entries.iter().position(|(h, k, _)| *h == hash && *k == key) {
+            Some(i) => (&mut entries[i].2, false),
+            None => {
+                self.len += 1;
+                entries.push((hash, key, default()));
+                let last = entries.len() - 1;
+                (&mut entries[last].2, true)
+            }
+        }
+    }
+
+    /// The value of `key` for writing, inserting `V::default()` first if it
+    /// is missing.
+    pub(crate) fn entry_or_default(&mut self, key: K) -> &mut V
+    where
+        V: Default,
+    {
+        self.entry_or_insert_with(key, V::default).0
+    }
+
+    /// Inserts or replaces the value of `key`, returning the old one.
+    pub(crate) fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let hash = self.hash(&key);
+        let entries = leaf_with_room(&mut self.root, hash);
+        match entries.iter_mut().find(|(h, k, _)| *h == hash && *k == key) {
+            Some((_, _, slot)) => Some(std::mem::replace(slot, value)),
+            None => {
+                self.len += 1;
+                entries.push((hash, key, value));
+                None
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, key: &K) -> Option<V> {
+        let hash = self.hash(key);
+        self.find(hash, key)?;
+        let entries = leaf_mut(&mut self.root, hash);
+        let i = entries.iter().position(|(h, k, _)| *h == hash && k == key)?;
+        self.len -= 1;
+        Some(entries.swap_remove(i).2)
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.root = Arc::new(TrieNode::Leaf(Vec::new()));
+        self.len = 0;
+    }
+
+    /// Sets every value to what `f` returns for it, copying the whole trie.
+    pub(crate) fn update_all(&mut self, mut f: impl FnMut(&K, &V) -> V) {
+        let mut stack = vec![&mut self.root];
+        while let Some(node) = stack.pop() {
+            match Arc::make_mut(node) {
+                TrieNode::Leaf(entries) => {
+                    for (_, k, v) in entries {
+                        *v = f(k, v);
+                    }
+                }
+                TrieNode::Branch(children) => stack.extend(children.iter_mut().flatten()),
+            }
+        }
+    }
+
+    fn find(&self, hash: u64, key: &K) -> Option<(&K, &V)> {
+        let mut node = self.root.as_ref();
+        let mut depth = 0;
+        loop {
+            match node {
+                TrieNode::Leaf(entries) => {
+                    return entries.iter().find(|(h, k, _)| *h == hash && k == key).map(|(_, k, v)| (k, v));
+                }
+                TrieNode::Branch(children) => {
+                    node = children[slot(hash, depth)].as_deref()?;
+                    depth += 1;
+                }
+            }
+        }
+    }
+}
+
+/// The leaf `hash` leads to, copying every node on the way. The caller has
+/// checked that it exists.
+fn leaf_mut<K: Clone, V: Clone>(root: &mut Arc<TrieNode<K, V>>, hash: u64) -> &mut Vec<(u64, K, V)> {
+    let mut node = Arc::make_mut(root);
+    let mut depth = 0;
+    loop {
+        match node {
+            TrieNode::Leaf(entries) => return entries,
+            TrieNode::Branch(children) => {
+                let child = children[slot(hash, depth)].as_mut().expect("path to an existing key");
+                node = Arc::make_mut(child);
+                depth += 1;
+            }
+        }
+    }
+}
+
+/// The leaf `hash` leads to, copying every node on the way and creating
+/// the missing ones. Full leaves on the way are split, so the returned one
+/// has room for a new entry unless it sits at `MAX_DEPTH`.
+fn leaf_with_room<K: Clone, V: Clone>(root: &mut Arc<TrieNode<K, V>>, hash: u64) -> &mut Vec<(u64, K, V)> {
+    let mut node = Arc::make_mut(root);
+    let mut depth = 0;
+    loop {
+        if let TrieNode::Leaf(entries) = node {
+            if entries.len() < LEAF_CAP || depth == MAX_DEPTH {
+                break;
+            }
+            *node = split(std::mem::take(entries), depth);
+        }
+        let TrieNode::Branch(children) = node else { unreachable!("full leaves were just split") };
+        let child = children[slot(hash, depth)].get_or_insert_with(|| Arc::new(TrieNode::Leaf(Vec::new())));
+        node = Arc::make_mut(child);
+        depth += 1;
+    }
+    let TrieNode::Leaf(entries) = node else { unreachable!("the walk stops at a leaf") };
+    entries
+}
+
+/// Turns the entries of a full leaf at `depth` into a branch.
+fn split<K, V>(entries: Vec<(u64, K, V)>, depth: u32) -> TrieNode<K, V> {
+    let mut buckets: Vec<Vec<(u64, K, V)>> = (0..WIDTH).map(|_| Vec::new()).collect();
+    for entry in entries {
+        buckets[slot(entry.0, depth)].push(entry);
+    }
+    let children = buckets.into_iter().map(|b| (!b.is_empty()).then(|| Arc::new(TrieNode::Leaf(b)))).collect();
+    TrieNode::Branch(children)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_matches_hashmap_and_clones_are_isolated() {
+        let mut map = PersistentMap::default();
+        let mut reference = HashMap::new();
+        for i in 0..2000u32 {
+            assert_eq!(map.insert(i % 700, i), reference.insert(i % 700, i));
+        }
+        let frozen = map.clone();
+        for i in (0..700).step_by(3) {
+            assert_eq!(map.remove(&i), reference.remove(&i));
+        }
+        *map.get_mut(&1).unwrap() += 1;
+        *map.entry_or_default(5000) += 7;
+
+        assert_eq!(map.len(), 700 - 234 + 1);
+        assert_eq!(map.get(&1), Some(&1402));
+        assert_eq!(map.get(&5000), Some(&7));
+        assert_eq!(map.remove(&3), None);
+        let mut pairs: Vec<_> = map.iter().map(|(k, v)| (*k, *v)).collect();
+        pairs.sort();
+        let mut expected: Vec<_> = reference.into_iter().collect();
+        expected.push((5000, 7));
+        expected.sort();
+        expected.iter_mut().filter(|(k, _)| *k == 1).for_each(|(_, v)| *v += 1);
+        assert_eq!(pairs, expected);
+
+        assert_eq!(frozen.len(), 700);
+        assert_eq!(frozen.get(&3), Some(&1403));
+        assert_eq!(frozen.get(&1), Some(&1401));
+        assert_eq!(frozen.iter().count(), 700);
+    }
+
+    #[test]
+    fn test_write_copies_only_the_path() {
+        let mut map = PersistentMap::default();
+        for i in 0..10_000 {
+            map.insert(i, i);
+        }
+        let frozen = map.clone();
+        map.insert(42, 0);
+        let (TrieNode::Branch(old), TrieNode::Branch(new)) = (frozen.root.as_ref(), map.root.as_ref()) else {
+            panic!("a map this size has a branch at the root");
+        };
+        let shared = old.iter().zip(new).filter(|(a, b)| match (a, b) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            _ => false,
+        });
+        assert_eq!(shared.count(), WIDTH - 1);
+        assert_eq!(frozen.get(&42), Some(&42));
+    }
+}
@@ -0,0 +1,38 @@
+use crate::core::error::GraphError;
+
+/// Which edges `add_edge` accepts, fixed when the graph is constructed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EdgePolicy {
+    /// Allow more than one edge between the same ordered pair of nodes
+    /// (the same unordered pair for undirected graphs).
+    pub allow_parallel: bool,
+    /// Allow edges from a node to itself.
+    pub allow_self_loops: bool,
+}
+
+impl EdgePolicy {
+    /// Accepts every edge. This is the default and matches the behavior of
+    /// `Graph::new`.
+    pub const MULTIGRAPH: EdgePolicy = EdgePolicy { allow_parallel: true, allow_self_loops: true };
+
+    /// No parallel edges and no self-loops.
+    pub const SIMPLE: EdgePolicy = EdgePolicy { allow_parallel: false, allow_self_loops: false };
+
+    /// Checks a new edge `u -> v` against the policy. `exists` is only
+    /// called when parallel edges are forbidden.
+    pub(crate) fn check<N: PartialEq>(&self, u: &N, v: &N, exists: impl FnOnce() -> bool) -> Result<(), GraphError> {
+        if !self.allow_self_loops && u == v {
+            return Err(GraphError::SelfLoop);
+        }
+        if !self.allow_parallel && exists() {
+            return Err(GraphError::DuplicateEdge);
+        }
+        Ok(())
+    }
+}
+
+impl Default for EdgePolicy {
+    fn default() -> Self {
+        EdgePolicy::MULTIGRAPH
+    }
+}
@@ -0,0 +1,409 @@
+use std::fmt;
+use std::hash::Hash;
+use std::sync::Arc;
+
+use crate::core::edge::{EdgeId, EdgeRef};
+use crate::core::persistent::PersistentMap;
+use crate::core::traits::{scan_in_edges, GraphBase};
+use crate::core::weight::Weight;
+
+/// Adjacency map whose per-node lists sit behind their own `Arc`.
+///
+/// Cloning it is O(1). Nodes live in a `PersistentMap`, so the first write
+/// after a clone copies only the O(log V) trie nodes on the way to the
+/// touched node, and each list is only copied when it is itself written
+/// to: a graph and its snapshots share everything neither side changed.
+#[derive(Debug)]
+pub(crate) struct SharedAdjacency<N, E>(PersistentMap<N, Arc<Vec<E>>>);
+
+impl<N, E> Clone for SharedAdjacency<N, E> {
+    fn clone(&self) -> Self {
+        SharedAdjacency(self.0.clone())
+    }
+}
+
+impl<N, E> Default for SharedAdjacency<N, E> {
+    fn default() -> Self {
+        SharedAdjacency(PersistentMap::default())
+    }
+}
+
+impl<N: Eq + Hash + Clone, E: Clone> SharedAdjacency<N, E> {
+    pub(crate) fn get(&self, node: &N) -> Option<&Vec<E>> {
+        self.0.get(node).map(Arc::as_ref)
+    }
+
+    pub(crate) fn get_key_value(&self, node: &N) -> Option<(&N, &Vec<E>)> {
+        self.0.get_key_value(node).map(|(n, list)| (n, list.as_ref()))
+    }
+
+    pub(crate) fn contains_key(&self, node: &N) -> bool {
+        self.0.contains_key(node)
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub(crate) fn keys(&self) -> impl Iterator<Item = &N> {
+        self.0.keys()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&N, &Vec<E>)> {
+        self.0.iter().map(|(n, list)| (n, list.as_ref()))
+    }
+
+    pub(crate) fn values(&self) -> impl Iterator<Item = &Vec<E>> {
+        self.0.values().map(Arc::as_ref)
+    }
+
+    /// The list of `node` for writing, copied first if a snapshot shares it.
+    pub(crate) fn get_mut(&mut self, node: &N) -> Option<&mut Vec<E>> {
+        self.0.get_mut(node).map(Arc::make_mut)
+    }
+
+    /// `get_mut`, inserting an empty list if `node` has none.
+    pub(crate) fn entry_or_default(&mut self, node: N) -> &mut Vec<E> {
+        Arc::make_mut(self.0.entry_or_default(node))
+    }
+
+    /// Adds an empty list for `node` unless it has one. Returns whether it
+    /// was added.
+    pub(crate) fn add_key(&mut self, node: N) -> bool {
+        self.0.entry_or_insert_with(node, Arc::default).1
+    }
+
+    pub(crate) fn insert(&mut self, node: N, list: Vec<E>) {
+        self.0.insert(node, Arc::new(list));
+    }
+
+    pub(crate) fn remove(&mut self, node: &N) {
+        self.0.remove(node);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.0.clear();
+    }
+
+    /// Empties every list but keeps the keys, without copying shared lists
+    /// just to clear them.
+    pub(crate) fn clear_lists(&mut self) {
+        self.0.update_all(|_, _| Arc::default());
+    }
+}
+
+/// Payload map that snapshots and clones share until the next write.
+///
+/// Each payload sits behind its own `Arc` in a `PersistentMap`, so sharing
+/// is O(1) and a write copies the O(log n) path to the payload plus, if a
+/// snapshot still holds it, the payload itself. Handing a payload out for
+/// writing or by value may copy it, so those methods need `V: Clone`;
+/// storing and dropping payloads does not.
+pub(crate) struct SharedPayloads<K, V> {
+    map: PersistentMap<K, Arc<V>>,
+}
+
+impl<K, V> Default for SharedPayloads<K, V> {
+    fn default() -> Self {
+        SharedPayloads { map: PersistentMap::default() }
+    }
+}
+
+/// Shares every payload; later writes on either side copy what they touch.
+impl<K: Clone, V: Clone> Clone for SharedPayloads<K, V> {
+    fn clone(&self) -> Self {
+        SharedPayloads { map: self.map.clone() }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for SharedPayloads<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V> SharedPayloads<K, V> {
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map.iter().map(|(k, v)| (k, v.as_ref()))
+    }
+}
+
+impl<K: Eq + Hash + Clone, V> FromIterator<(K, V)> for SharedPayloads<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut payloads = SharedPayloads::default();
+        for (k, v) in iter {
+            payloads.set(k, v);
+        }
+        payloads
+    }
+}
+
+impl<K: Eq + Hash + Clone, V> SharedPayloads<K, V> {
+    pub(crate) fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key).map(Arc::as_ref)
+    }
+
+    /// Stores `value` under `key`, dropping the payload it replaces.
+    pub(crate) fn set(&mut self, key: K, value: V) {
+        self.map.insert(key, Arc::new(value));
+    }
+
+    /// Drops the payload of `key`, if any.
+    pub(crate) fn discard(&mut self, key: &K) {
+        self.map.remove(key);
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.map.clear();
+    }
+}
+
+impl<K: Eq + Hash + Clone, V: Clone> SharedPayloads<K, V> {
+    /// The payload of `key` for writing, copying it first if a snapshot
+    /// holds it too.
+    pub(crate) fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.map.get_mut(key).map(Arc::make_mut)
+    }
+
+    pub(crate) fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.map.insert(key, Arc::new(value)).map(Arc::unwrap_or_clone)
+    }
+
+    pub(crate) fn remove(&mut self, key: &K) -> Option<V> {
+        self.map.remove(key).map(Arc::unwrap_or_clone)
+    }
+}
+
+/// Frozen copy of a `Graph`'s structure, returned by `Graph::snapshot`.
+///
+/// Taking one is O(1): it shares the graph's adjacency lists, and later
+/// mutations of the graph copy what they touch instead of changing them,
+/// so a reader holding the snapshot keeps seeing the graph as it was. It
+/// implements `GraphBase`, so every traversal runs on it. Node and edge
+/// payloads are shared the same way, as of the snapshot.
+#[derive(Debug, Clone)]
+pub struct GraphSnapshot<N, NData = (), EData = ()> {
+    pub(crate) adj: SharedAdjacency<N, (N, EdgeId)>,
+    pub(crate) rev: Option<SharedAdjacency<N, (N, EdgeId)>>,
+    pub(crate) node_data: SharedPayloads<N, NData>,
+    pub(crate) edge_data: SharedPayloads<EdgeId, EData>,
+    pub(crate) is_directed: bool,
+    pub(crate) edge_count: usize,
+    pub(crate) version: u64,
+}
+
+impl<N: Eq + Hash + Clone, NData, EData> GraphSnapshot<N, NData, EData> {
+    /// Version of the graph when the snapshot was taken.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn node_data(&self, node: &N) -> Option<&NData> {
+        self.node_data.get(node)
+    }
+
+    pub fn edge_data(&self, id: EdgeId) -> Option<&EData> {
+        self.edge_data.get(&id)
+    }
+
+    /// Same as `Graph::incoming`.
+    fn incoming(&self, node: &N) -> Option<&[(N, EdgeId)]> {
+        let map = if self.is_directed { self.rev.as_ref()? } else { &self.adj };
+        Some(map.get(node).map_or(&[], Vec::as_slice))
+    }
+}
+
+impl<N: Eq + Hash + Clone, NData, EData> GraphBase for GraphSnapshot<N, NData, EData> {
+    type Node = N;
+    type Weight = ();
+
+    fn is_directed(&self) -> bool {
+        self.is_directed
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = N> + '_ {
+        self.adj.keys().cloned()
+    }
+
+    fn neighbors<'a>(&'a self, node: &N) -> impl Iterator<Item = N> + use<'a, N, NData, EData> {
+        self.adj.get(node).into_iter().flatten().map(|(v, _)| v.clone())
+    }
+
+    fn out_edges<'a>(&'a self, node: &N) -> impl Iterator<Item = EdgeRef<N>> + use<'a, N, NData, EData> {
+        self.adj.get_key_value(node).into_iter().flat_map(|(u, out)| {
+            out.iter().map(move |(v, id)| EdgeRef { source: u.clone(), target: v.clone(), weight: (), id: *id })
+        })
+    }
+
+    fn in_edges<'a>(&'a self, node: &N) -> impl Iterator<Item = EdgeRef<N>> + use<'a, N, NData, EData> {
+        let target = node.clone();
+        let direct = self.incoming(node).map(move |incoming| {
+            incoming.iter().map(move |(u, id)| EdgeRef { source: u.clone(), target: target.clone(), weight: (), id: *id })
+        });
+        let scan = direct.is_none().then(|| scan_in_edges(self, node));
+        direct.into_iter().flatten().chain(scan.into_iter().flatten())
+    }
+
+    fn contains_node(&self, node: &N) -> bool {
+        self.adj.contains_key(node)
+    }
+
+    fn node_count(&self) -> usize {
+        self.adj.len()
+    }
+
+    fn edge_count(&self) -> usize {
+        self.edge_count
+    }
+}
+
+/// Frozen copy of a `WeightedGraph`'s structure, returned by
+/// `WeightedGraph::snapshot`. Shares adjacency lists with the graph the
+/// same way `GraphSnapshot` does.
+#[derive(Debug, Clone)]
+pub struct WeightedSnapshot<N, W> {
+    pub(crate) adj: SharedAdjacency<N, (N, W, EdgeId)>,
+    pub(crate) rev: Option<SharedAdjacency<N, (N, W, EdgeId)>>,
+    pub(crate) is_directed: bool,
+    pub(crate) edge_count: usize,
+    pub(crate) version: u64,
+}
+
+impl<N: Eq + Hash + Clone, W: Weight> WeightedSnapshot<N, W> {
+    /// Version of the graph when the snapshot was taken.
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    /// Same as `WeightedGraph::incoming`.
+    fn incoming(&self, node: &N) -> Option<&[(N, W, EdgeId)]> {
+        let map = if self.is_directed { self.rev.as_ref()? } else { &self.adj };
+        Some(map.get(node).map_or(&[], Vec::as_slice))
+    }
+}
+
+impl<N: Eq + Hash + Clone, W: Weight> GraphBase for WeightedSnapshot<N, W> {
+    type Node = N;
+    type Weight = W;
+
+    fn is_directed(&self) -> bool {
+        self.is_directed
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = N> + '_ {
+        self.adj.keys().cloned()
+    }
+
+    fn neighbors<'a>(&'a self, node: &N) -> impl Iterator<Item = N> + use<'a, N, W> {
+        self.adj.get(node).into_iter().flatten().map(|(v, _, _)| v.clone())
+    }
+
+    fn out_edges<'a>(&'a self, node: &N) -> impl Iterator<Item = EdgeRef<N, W>> + use<'a, N, W> {
+        self.adj.get_key_value(node).into_iter().flat_map(|(u, out)| {
+            out.iter().map(move |(v, w, id)| EdgeRef { source: u.clone(), target: v.clone(), weight: *w, id: *id })
+        })
+    }
+
+    fn in_edges<'a>(&'a self, node: &N) -> impl Iterator<Item = EdgeRef<N, W>> + use<'a, N, W> {
+        let target = node.clone();
+        let direct = self.incoming(node).map(move |incoming| {
+            incoming.iter().map(move |(u, w, id)| EdgeRef { source: u.clone(), target: target.clone(), weight: *w, id: *id })
+        });
+        let scan = direct.is_none().then(|| scan_in_edges(self, node));
+        direct.into_iter().flatten().chain(scan.into_iter().flatten())
+    }
+
+    fn contains_node(&self, node: &N) -> bool {
+        self.adj.contains_key(node)
+    }
+
+    fn node_count(&self) -> usize {
+        self.adj.len()
+    }
+
+    fn edge_count(&self) -> usize {
+        self.edge_count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algos::bfs::BfsExt;
+    use crate::core::graph::Graph;
+    use crate::core::weightedgraph::WeightedGraph;
+
+    #[test]
+    fn test_snapshot_is_isolated_from_writes() {
+        let mut graph = Graph::new(true);
+        graph.enable_reverse_index();
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(2, 3).unwrap();
+        let snapshot = graph.snapshot();
+
+        graph.add_edge(3, 4).unwrap();
+        graph.remove_node(&2).unwrap();
+        graph.clear_edges();
+
+        assert_eq!(snapshot.version(), 5);
+        assert_eq!(snapshot.bfs(1).unwrap(), [1, 2, 3]);
+        assert_eq!(snapshot.node_count(), 3);
+        assert_eq!(snapshot.edge_count(), 2);
+        assert_eq!(snapshot.predecessors(&3).collect::<Vec<_>>(), [2]);
+        assert_eq!(graph.bfs(1).unwrap(), [1]);
+        assert_eq!(graph.validate(), Ok(()));
+    }
+
+    #[test]
+    fn test_untouched_lists_stay_shared() {
+        let mut graph = Graph::new(false);
+        graph.add_edge("a", "b").unwrap();
+        graph.add_edge("c", "d").unwrap();
+        let snapshot = graph.snapshot();
+        assert!(snapshot.adj.0.ptr_eq(&graph.snapshot().adj.0));
+
+        graph.add_edge("a", "e").unwrap();
+        let after = graph.snapshot();
+        assert!(!snapshot.adj.0.ptr_eq(&after.adj.0));
+        assert!(Arc::ptr_eq(snapshot.adj.0.get(&"c").unwrap(), after.adj.0.get(&"c").unwrap()));
+        assert!(!Arc::ptr_eq(snapshot.adj.0.get(&"a").unwrap(), after.adj.0.get(&"a").unwrap()));
+        assert_eq!(snapshot.neighbors(&"a").collect::<Vec<_>>(), ["b"]);
+        assert!(!snapshot.contains_node(&"e"));
+    }
+
+    #[test]
+    fn test_snapshot_keeps_payloads() {
+        let mut graph: Graph<&str, &str, u32> = Graph::attributed(true, Default::default());
+        graph.add_node_with_data("api", "team-a");
+        let id = graph.add_edge_with_data("api", "db", 5).unwrap();
+        let snapshot = graph.snapshot();
+        assert!(snapshot.node_data.map.ptr_eq(&graph.snapshot().node_data.map));
+
+        *graph.edge_data_mut(id).unwrap() = 9;
+        graph.add_node_with_data("api", "team-b");
+        graph.remove_node(&"db").unwrap();
+
+        assert_eq!(snapshot.node_data(&"api"), Some(&"team-a"));
+        assert_eq!(snapshot.edge_data(id), Some(&5));
+        assert_eq!(graph.node_data(&"api"), Some(&"team-b"));
+        assert_eq!(graph.edge_data(id), None);
+        let later = graph.snapshot();
+        assert_eq!(later.node_data(&"api"), Some(&"team-b"));
+        assert!(!snapshot.node_data.map.ptr_eq(&later.node_data.map));
+    }
+
+    #[test]
+    fn test_weighted_snapshot() {
+        let mut graph = WeightedGraph::new(false);
+        let ab = graph.add_edge(1, 2, 4).unwrap();
+        let snapshot = graph.snapshot();
+        graph.set_weight(ab, 9).unwrap();
+        graph.add_edge(2, 3, 1).unwrap();
+
+        let weights: Vec<_> = snapshot.out_edges(&2).map(|e| (e.target, e.weight)).collect();
+        assert_eq!(weights, [(1, 4)]);
+        assert_eq!(snapshot.in_edges(&2).map(|e| e.weight).collect::<Vec<_>>(), [4]);
+        assert_eq!(graph.weight(ab), Some(9));
+        assert_eq!(snapshot.bfs_distances(1).unwrap().len(), 2);
+    }
+}
@@ -0,0 +1,170 @@
+use std::hash::Hash;
+
+use crate::core::edge::{EdgeId, EdgeRef};
+use crate::core::error::GraphError;
+use crate::core::policy::EdgePolicy;
+use crate::core::traits::GraphBase;
+use crate::core::views::{EdgeFiltered, ViewExt};
+use crate::core::weight::Weight;
+use crate::core::weightedgraph::WeightedGraph;
+
+/// Graph whose edges are timestamped contacts, e.g. one call between two
+/// services.
+///
+/// The timestamp is stored as the edge weight of a `WeightedGraph`, so `T`
+/// is any `Weight` and parallel edges record repeated contacts. Use
+/// `TemporalExt` for time-respecting traversals, `at` and `window` for the
+/// graph as seen at a time, and `expire_before` to slide a window forward.
+#[derive(Debug, Clone)]
+pub struct TemporalGraph<N = i32, T = i64> {
+    graph: WeightedGraph<N, T>,
+}
+
+impl<N: Eq + Hash + Clone, T: Weight> TemporalGraph<N, T> {
+    pub fn new(is_directed: bool) -> Self {
+        TemporalGraph { graph: WeightedGraph::new(is_directed) }
+    }
+
+    /// Creates an empty graph whose `add_edge` enforces `policy`.
+    pub fn with_policy(is_directed: bool, policy: EdgePolicy) -> Self {
+        TemporalGraph { graph: WeightedGraph::with_policy(is_directed, policy) }
+    }
+
+    /// The underlying graph, with timestamps as weights.
+    pub fn graph(&self) -> &WeightedGraph<N, T> {
+        &self.graph
+    }
+
+    /// Adds an isolated node. Returns `false` if it was already present.
+    pub fn add_node(&mut self, node: N) -> bool {
+        self.graph.add_node(node)
+    }
+
+    /// Adds a `u -> v` contact at `time`, creating missing nodes. Fails with
+    /// `InvalidWeight` if `time` is NaN, or if the policy rejects the edge.
+    pub fn add_edge(&mut self, u: N, v: N, time: T) -> Result<EdgeId, GraphError> {
+        self.graph.add_edge(u, v, time)
+    }
+
+    /// Timestamp of the edge with the given id.
+    pub fn time(&self, id: EdgeId) -> Option<T> {
+        self.graph.weight(id)
+    }
+
+    pub fn remove_edge_by_id(&mut self, id: EdgeId) -> Result<EdgeRef<N, T>, GraphError> {
+        self.graph.remove_edge_by_id(id)
+    }
+
+    /// View of the graph as it stood at `time`: every edge with a timestamp
+    /// at or before it.
+    pub fn at(&self, time: T) -> EdgeFiltered<'_, WeightedGraph<N, T>, impl Fn(&EdgeRef<N, T>) -> bool + use<N, T>> {
+        self.graph.filter_edges(move |e: &EdgeRef<N, T>| e.weight <= time)
+    }
+
+    /// View of the edges with `from <= timestamp < to`.
+    pub fn window(&self, from: T, to: T) -> EdgeFiltered<'_, WeightedGraph<N, T>, impl Fn(&EdgeRef<N, T>) -> bool + use<N, T>> {
+        self.graph.filter_edges(move |e: &EdgeRef<N, T>| from <= e.weight && e.weight < to)
+    }
+
+    /// Removes every edge timestamped before `cutoff`, keeping the nodes.
+    /// Calling it with `now - width` as events arrive keeps a sliding window
+    /// of the last `width`. Returns the number of edges removed.
+    pub fn expire_before(&mut self, cutoff: T) -> usize {
+        self.graph.retain_edges(|_, _, &time| time >= cutoff)
+    }
+}
+
+impl<N: Eq + Hash + Clone, T: Weight> GraphBase for TemporalGraph<N, T> {
+    type Node = N;
+    type Weight = T;
+
+    fn is_directed(&self) -> bool {
+        self.graph.is_directed()
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = N> + '_ {
+        GraphBase::nodes(&self.graph)
+    }
+
+    fn neighbors<'a>(&'a self, node: &N) -> impl Iterator<Item = N> + use<'a, N, T> {
+        GraphBase::neighbors(&self.graph, node)
+    }
+
+    fn out_edges<'a>(&'a self, node: &N) -> impl Iterator<Item = EdgeRef<N, T>> + use<'a, N, T> {
+        GraphBase::out_edges(&self.graph, node)
+    }
+
+    fn in_edges<'a>(&'a self, node: &N) -> impl Iterator<Item = EdgeRef<N, T>> + use<'a, N, T> {
+        GraphBase::in_edges(&self.graph, node)
+    }
+
+    fn edges(&self) -> impl Iterator<Item = EdgeRef<N, T>> + '_ {
+        GraphBase::edges(&self.graph)
+    }
+
+    fn contains_node(&self, node: &N) -> bool {
+        self.graph.contains_node(node)
+    }
+
+    fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algos::bfs::BfsExt;
+    use crate::algos::temporal::TemporalExt;
+
+    fn calls() -> TemporalGraph<&'static str> {
+        let mut graph = TemporalGraph::new(true);
+        graph.add_edge("gateway", "auth", 1).unwrap();
+        graph.add_edge("auth", "db", 3).unwrap();
+        graph.add_edge("gateway", "orders", 4).unwrap();
+        graph.add_edge("orders", "auth", 2).unwrap();
+        graph.add_edge("orders", "db", 4).unwrap();
+        graph.add_edge("db", "cache", 9).unwrap();
+        graph
+    }
+
+    #[test]
+    fn test_earliest_arrival_respects_time() {
+        let graph = calls();
+        let arrival = graph.earliest_arrival("gateway", 0).unwrap();
+        assert_eq!(arrival["db"], 3);
+        assert_eq!(arrival["orders"], 4);
+        assert_eq!(arrival["cache"], 9);
+
+        // leaving too late for gateway -> auth, and orders -> auth is in the past by then
+        let late = graph.earliest_arrival("gateway", 2).unwrap();
+        assert!(!late.contains_key("auth"));
+        assert_eq!(late["db"], 4);
+
+        let path = graph.earliest_arrival_path("gateway", "db", 2).unwrap().unwrap();
+        assert_eq!(path.iter().map(|e| (e.source, e.target, e.weight)).collect::<Vec<_>>(), [("gateway", "orders", 4), ("orders", "db", 4)]);
+        assert_eq!(graph.earliest_arrival_path("db", "gateway", 0).unwrap(), None);
+        assert_eq!(graph.earliest_arrival_path("db", "db", 0).unwrap(), Some(Vec::new()));
+        assert_eq!(graph.earliest_arrival("nowhere", 0), Err(GraphError::NodeNotFound));
+    }
+
+    #[test]
+    fn test_time_views_and_expiry() {
+        let mut graph = calls();
+        assert_eq!(graph.at(3).bfs("gateway").unwrap(), ["gateway", "auth", "db"]);
+        assert_eq!(graph.window(2, 5).edge_count(), 4);
+        assert_eq!(graph.window(2, 5).earliest_arrival("orders", 0).unwrap()["db"], 3);
+
+        assert_eq!(graph.expire_before(3), 2);
+        assert_eq!(graph.edge_count(), 4);
+        assert_eq!(graph.node_count(), 5);
+        assert!(graph.edges().all(|e| e.weight >= 3));
+        let late = graph.add_edge("a", "b", 1).unwrap();
+        assert_eq!(graph.time(late), Some(1));
+        assert_eq!(graph.expire_before(3), 1);
+    }
+}
@@ -0,0 +1,256 @@
+use std::collections::HashSet;
+use std::hash::Hash;
+
+use crate::core::error::GraphError;
+use crate::core::edge::EdgeRef;
+use crate::core::graph::Graph;
+use crate::core::weight::Weight;
+use crate::core::weightedgraph::WeightedGraph;
+
+/// Read-only view of a graph's structure, shared by every graph backend.
+///
+/// The traversals in `algos` are written against this trait, so anything
+/// that can list its nodes and the edges leaving a node gets them for free.
+pub trait GraphBase {
+    type Node: Eq + Hash + Clone;
+    /// Edge weight, `()` for unweighted backends.
+    type Weight: Clone;
+
+    fn is_directed(&self) -> bool;
+
+    /// Every node that has an adjacency entry, in no particular order.
+    fn nodes(&self) -> impl Iterator<Item = Self::Node> + '_;
+
+    /// Out-neighbors of `node`, empty if the node is unknown. Parallel edges
+    /// yield the same neighbor more than once.
+    fn neighbors<'a>(&'a self, node: &Self::Node) -> impl Iterator<Item = Self::Node> + use<'a, Self>;
+
+    /// Edges leaving `node`, in the same order as `neighbors`.
+    fn out_edges<'a>(&'a self, node: &Self::Node) -> impl Iterator<Item = EdgeRef<Self::Node, Self::Weight>> + use<'a, Self>;
+
+    /// Edges entering `node`, each with `target == node`. Undirected graphs
+    /// return the out-edges turned around. The default scans every
+    /// adjacency list; backends with an incoming-edge index override it.
+    fn in_edges<'a>(&'a self, node: &Self::Node) -> impl Iterator<Item = EdgeRef<Self::Node, Self::Weight>> + use<'a, Self> {
+        let (direct, scan) = if self.is_directed() {
+            (None, Some(scan_in_edges(self, node)))
+        } else {
+            (Some(self.out_edges(node).map(EdgeRef::reversed)), None)
+        };
+        direct.into_iter().flatten().chain(scan.into_iter().flatten())
+    }
+
+    /// Sources of the edges entering `node`, once per edge.
+    fn predecessors<'a>(&'a self, node: &Self::Node) -> impl Iterator<Item = Self::Node> + use<'a, Self> {
+        self.in_edges(node).map(|e| e.source)
+    }
+
+    /// Every edge. Undirected edges, self-loops included, are reported once
+    /// even though they are stored in both endpoints' adjacency.
+    fn edges(&self) -> impl Iterator<Item = EdgeRef<Self::Node, Self::Weight>> + '_ {
+        let directed = self.is_directed();
+        let mut seen = HashSet::new();
+        self.nodes()
+            .flat_map(move |u| self.out_edges(&u))
+            .filter(move |e| directed || seen.insert(e.id))
+    }
+
+    fn contains_node(&self, node: &Self::Node) -> bool {
+        self.nodes().any(|n| n == *node)
+    }
+
+    fn node_count(&self) -> usize {
+        self.nodes().count()
+    }
+
+    /// Number of edges, counting each undirected edge once.
+    fn edge_count(&self) -> usize {
+        self.edges().count()
+    }
+
+    /// Position of `node` in `0..node_bound()` for backends that number
+    /// their nodes densely. Traversals use it to keep visited flags in a
+    /// vector instead of a hash set.
+    fn node_index(&self, _node: &Self::Node) -> Option<usize> {
+        None
+    }
+
+    /// Upper bound of `node_index`, or `None` if nodes are not indexed.
+    fn node_bound(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// The edge type reported by a graph's `out_edges` and `in_edges`.
+pub type EdgeOf<G> = EdgeRef<<G as GraphBase>::Node, <G as GraphBase>::Weight>;
+
+/// Fails with `NodeNotFound` unless `node` is in `graph`.
+pub(crate) fn ensure_node<G: GraphBase + ?Sized>(graph: &G, node: &G::Node) -> Result<(), GraphError> {
+    if graph.contains_node(node) {
+        Ok(())
+    } else {
+        Err(GraphError::NodeNotFound)
+    }
+}
+
+/// In-edges found by checking every node's out-edges for `node`.
+pub(crate) fn scan_in_edges<'a, G: GraphBase + ?Sized>(graph: &'a G, node: &G::Node) -> impl Iterator<Item = EdgeRef<G::Node, G::Weight>> + use<'a, G> {
+    let target = node.clone();
+    graph
+        .nodes()
+        .flat_map(move |u| graph.out_edges(&u))
+        .filter(move |e| e.target == target)
+}
+
+impl<N: Eq + Hash + Clone, NData, EData> GraphBase for Graph<N, NData, EData> {
+    type Node = N;
+    type Weight = ();
+
+    fn is_directed(&self) -> bool {
+        Graph::is_directed(self)
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = N> + '_ {
+        Graph::nodes(self).cloned()
+    }
+
+    fn neighbors<'a>(&'a self, node: &N) -> impl Iterator<Item = N> + use<'a, N, NData, EData> {
+        Graph::neighbors(self, node).cloned()
+    }
+
+    fn out_edges<'a>(&'a self, node: &N) -> impl Iterator<Item = EdgeRef<N>> + use<'a, N, NData, EData> {
+        Graph::out_edges(self, node).map(EdgeRef::cloned)
+    }
+
+    fn in_edges<'a>(&'a self, node: &N) -> impl Iterator<Item = EdgeRef<N>> + use<'a, N, NData, EData> {
+        let direct = self.incoming(node).map(|incoming| {
+            let target = node.clone();
+            incoming.iter().map(move |(u, id)| EdgeRef { source: u.clone(), target: target.clone(), weight: (), id: *id })
+        });
+        let scan = direct.is_none().then(|| scan_in_edges(self, node));
+        direct.into_iter().flatten().chain(scan.into_iter().flatten())
+    }
+
+    fn predecessors<'a>(&'a self, node: &N) -> impl Iterator<Item = N> + use<'a, N, NData, EData> {
+        let direct = self.incoming(node);
+        let scan = direct.is_none().then(|| scan_in_edges(self, node));
+        direct.into_iter().flatten().map(|(u, _)| u.clone()).chain(scan.into_iter().flatten().map(|e| e.source))
+    }
+
+    fn edges(&self) -> impl Iterator<Item = EdgeRef<N>> + '_ {
+        Graph::edges(self).map(EdgeRef::cloned)
+    }
+
+    fn contains_node(&self, node: &N) -> bool {
+        Graph::contains_node(self, node)
+    }
+
+    fn node_count(&self) -> usize {
+        Graph::node_count(self)
+    }
+
+    fn edge_count(&self) -> usize {
+        Graph::edge_count(self)
+    }
+}
+
+impl<N: Eq + Hash + Clone, W: Weight> GraphBase for WeightedGraph<N, W> {
+    type Node = N;
+    type Weight = W;
+
+    fn is_directed(&self) -> bool {
+        WeightedGraph::is_directed(self)
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = N> + '_ {
+        WeightedGraph::nodes(self).cloned()
+    }
+
+    fn neighbors<'a>(&'a self, node: &N) -> impl Iterator<Item = N> + use<'a, N, W> {
+        WeightedGraph::neighbors(self, node).map(|(v, _)| v.clone())
+    }
+
+    fn out_edges<'a>(&'a self, node: &N) -> impl Iterator<Item = EdgeRef<N, W>> + use<'a, N, W> {
+        WeightedGraph::out_edges(self, node).map(EdgeRef::cloned)
+    }
+
+    fn in_edges<'a>(&'a self, node: &N) -> impl Iterator<Item = EdgeRef<N, W>> + use<'a, N, W> {
+        let direct = self.incoming(node).map(|incoming| {
+            let target = node.clone();
+            incoming.iter().map(move |(u, w, id)| EdgeRef { source: u.clone(), target: target.clone(), weight: *w, id: *id })
+        });
+        let scan = direct.is_none().then(|| scan_in_edges(self, node));
+        direct.into_iter().flatten().chain(scan.into_iter().flatten())
+    }
+
+    fn predecessors<'a>(&'a self, node: &N) -> impl Iterator<Item = N> + use<'a, N, W> {
+        let direct = self.incoming(node);
+        let scan = direct.is_none().then(|| scan_in_edges(self, node));
+        direct.into_iter().flatten().map(|(u, _, _)| u.clone()).chain(scan.into_iter().flatten().map(|e| e.source))
+    }
+
+    fn edges(&self) -> impl Iterator<Item = EdgeRef<N, W>> + '_ {
+        WeightedGraph::edges(self).map(EdgeRef::cloned)
+    }
+
+    fn contains_node(&self, node: &N) -> bool {
+        WeightedGraph::contains_node(self, node)
+    }
+
+    fn node_count(&self) -> usize {
+        WeightedGraph::node_count(self)
+    }
+
+    fn edge_count(&self) -> usize {
+        WeightedGraph::edge_count(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_edges_reported_once() {
+        let mut graph = Graph::new(false);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(2, 3).unwrap();
+        graph.add_edge(3, 3).unwrap();
+
+        let mut edges: Vec<_> = GraphBase::edges(&graph)
+            .map(|e| if e.source <= e.target { (e.source, e.target) } else { (e.target, e.source) })
+            .collect();
+        edges.sort();
+        assert_eq!(edges, vec![(1, 2), (2, 3), (3, 3)]);
+    }
+
+    #[test]
+    fn test_edges_directed() {
+        let mut graph: WeightedGraph<i32, i32> = WeightedGraph::new(true);
+        graph.add_edge(1, 2, 5).unwrap();
+        graph.add_edge(2, 1, 7).unwrap();
+
+        let mut edges: Vec<_> = GraphBase::edges(&graph).map(|e| (e.source, e.target, e.weight)).collect();
+        edges.sort();
+        assert_eq!(edges, vec![(1, 2, 5), (2, 1, 7)]);
+        assert!(graph.is_directed());
+    }
+
+    #[test]
+    fn test_in_edges_with_and_without_index() {
+        let mut graph = Graph::new(true);
+        let a = graph.add_edge(1, 3).unwrap();
+        let b = graph.add_edge(2, 3).unwrap();
+        graph.add_edge(3, 1).unwrap();
+
+        let mut scanned: Vec<_> = graph.in_edges(&3).map(|e| (e.source, e.id)).collect();
+        scanned.sort();
+        assert_eq!(scanned, vec![(1, a), (2, b)]);
+
+        graph.enable_reverse_index();
+        let mut indexed: Vec<_> = graph.in_edges(&3).map(|e| (e.source, e.id)).collect();
+        indexed.sort();
+        assert_eq!(indexed, scanned);
+        assert!(graph.in_edges(&3).all(|e| e.target == 3));
+    }
+}
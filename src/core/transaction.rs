@@ -0,0 +1,464 @@
+use std::hash::Hash;
+
+use crate::core::edge::{EdgeId, EdgeRef};
+use crate::core::error::GraphError;
+use crate::core::graph::Graph;
+use crate::core::traits::{ensure_node, EdgeOf, GraphBase};
+use crate::core::weight::Weight;
+use crate::core::weightedgraph::WeightedGraph;
+
+/// One reversible step of a transaction, undo or redo.
+///
+/// Applying an edit with `Editable::apply_edit` returns the edit that
+/// reverts it, so a journal of those inverses replayed backwards restores
+/// the graph, payloads and edge ids included.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit<N, W = (), NData = (), EData = ()> {
+    InsertNode { node: N, data: Option<NData> },
+    /// Removes an isolated node. Fails with `NodeHasEdges` otherwise, since
+    /// the inverse could not restore the edges; delete those first.
+    DeleteNode { node: N },
+    /// Stores `edge` under its own id, creating missing endpoints.
+    InsertEdge { edge: EdgeRef<N, W>, data: Option<EData> },
+    DeleteEdge { id: EdgeId },
+    SetWeight { id: EdgeId, weight: W },
+}
+
+/// The `Edit` type of graph `G`.
+pub type EditOf<G> = Edit<<G as GraphBase>::Node, <G as GraphBase>::Weight, <G as Editable>::NodeData, <G as Editable>::EdgeData>;
+
+/// Graphs whose mutations can be journaled by `Transaction` and `History`.
+pub trait Editable: GraphBase {
+    type NodeData;
+    type EdgeData;
+
+    /// Performs `edit` and returns its inverse. Fails without changing
+    /// anything if a node or edge it refers to is missing, or one it would
+    /// insert is already there.
+    fn apply_edit(&mut self, edit: EditOf<Self>) -> Result<EditOf<Self>, GraphError>;
+}
+
+impl<N: Eq + Hash + Clone, NData: Clone, EData: Clone> Editable for Graph<N, NData, EData> {
+    type NodeData = NData;
+    type EdgeData = EData;
+
+    fn apply_edit(&mut self, edit: EditOf<Self>) -> Result<EditOf<Self>, GraphError> {
+        match edit {
+            Edit::InsertNode { node, data } => {
+                if self.contains_node(&node) {
+                    return Err(GraphError::DuplicateNode);
+                }
+                match data {
+                    Some(data) => {
+                        self.add_node_with_data(node.clone(), data);
+                    }
+                    None => {
+                        self.add_node(node.clone());
+                    }
+                }
+                Ok(Edit::DeleteNode { node })
+            }
+            Edit::DeleteNode { node } => {
+                if self.degree(&node)? > 0 {
+                    return Err(GraphError::NodeHasEdges);
+                }
+                let data = self.take_node_data(&node);
+                self.remove_node(&node)?;
+                Ok(Edit::InsertNode { node, data })
+            }
+            Edit::InsertEdge { edge, data } => {
+                if self.contains_edge(edge.id) {
+                    return Err(GraphError::DuplicateEdge);
+                }
+                self.insert_edge_with_id(edge.source, edge.target, edge.id);
+                if let Some(data) = data {
+                    self.set_edge_data(edge.id, data)?;
+                }
+                Ok(Edit::DeleteEdge { id: edge.id })
+            }
+            Edit::DeleteEdge { id } => {
+                if !self.contains_edge(id) {
+                    return Err(GraphError::EdgeNotFound);
+                }
+                let data = self.take_edge_data(id);
+                let edge = self.remove_edge_by_id(id)?;
+                Ok(Edit::InsertEdge { edge, data })
+            }
+            Edit::SetWeight { id, weight } => {
+                if !self.contains_edge(id) {
+                    return Err(GraphError::EdgeNotFound);
+                }
+                Ok(Edit::SetWeight { id, weight })
+            }
+        }
+    }
+}
+
+impl<N: Eq + Hash + Clone, W: Weight> Editable for WeightedGraph<N, W> {
+    type NodeData = ();
+    type EdgeData = ();
+
+    fn apply_edit(&mut self, edit: EditOf<Self>) -> Result<EditOf<Self>, GraphError> {
+        match edit {
+            Edit::InsertNode { node, .. } => {
+                if !self.add_node(node.clone()) {
+                    return Err(GraphError::DuplicateNode);
+                }
+                Ok(Edit::DeleteNode { node })
+            }
+            Edit::DeleteNode { node } => {
+                if self.degree(&node)? > 0 {
+                    return Err(GraphError::NodeHasEdges);
+                }
+                self.remove_node(&node)?;
+                Ok(Edit::InsertNode { node, data: None })
+            }
+            Edit::InsertEdge { edge, .. } => {
+                if self.contains_edge(edge.id) {
+                    return Err(GraphError::DuplicateEdge);
+                }
+                if !edge.weight.is_valid() {
+                    return Err(GraphError::InvalidWeight);
+                }
+                self.insert_edge_with_id(edge.source, edge.target, edge.weight, edge.id);
+                Ok(Edit::DeleteEdge { id: edge.id })
+            }
+            Edit::DeleteEdge { id } => {
+                let edge = self.remove_edge_by_id(id)?;
+                Ok(Edit::InsertEdge { edge, data: None })
+            }
+            Edit::SetWeight { id, weight } => {
+                let old = self.set_weight(id, weight)?;
+                Ok(Edit::SetWeight { id, weight: old })
+            }
+        }
+    }
+}
+
+/// A batch of edits to a borrowed graph that either all stay or are all
+/// undone.
+///
+/// Every mutation goes through the graph's own methods, so policies,
+/// reverse indexes and the change log behave as usual, and its inverse is
+/// journaled. `commit` keeps the edits; `rollback`, or dropping the
+/// transaction, replays the journal backwards. A failed mutation changes
+/// nothing, so the transaction stays usable after an error.
+pub struct Transaction<'g, G: Editable> {
+    graph: &'g mut G,
+    /// Inverses of the edits made so far, oldest first.
+    journal: Vec<EditOf<G>>,
+}
+
+impl<'g, G: Editable> Transaction<'g, G> {
+    pub fn new(graph: &'g mut G) -> Self {
+        Transaction { graph, journal: Vec::new() }
+    }
+
+    /// The graph with the edits made so far.
+    pub fn graph(&self) -> &G {
+        self.graph
+    }
+
+    /// Applies a raw `Edit`, journaling its inverse.
+    pub fn apply(&mut self, edit: EditOf<G>) -> Result<(), GraphError> {
+        let inverse = self.graph.apply_edit(edit)?;
+        self.journal.push(inverse);
+        Ok(())
+    }
+
+    /// Adds an isolated node. Returns `Ok(false)` if it was already
+    /// present, and the error if the graph rejected the edit.
+    pub fn add_node(&mut self, node: G::Node) -> Result<bool, GraphError> {
+        if self.graph.contains_node(&node) {
+            return Ok(false);
+        }
+        self.apply(Edit::InsertNode { node, data: None })?;
+        Ok(true)
+    }
+
+    /// Removes the edge with the given id, returning it.
+    pub fn remove_edge_by_id(&mut self, id: EdgeId) -> Result<EdgeOf<G>, GraphError> {
+        let inverse = self.graph.apply_edit(Edit::DeleteEdge { id })?;
+        let Edit::InsertEdge { edge, .. } = &inverse else {
+            unreachable!("the inverse of a deletion is an insertion");
+        };
+        let removed = edge.clone();
+        self.journal.push(inverse);
+        Ok(removed)
+    }
+
+    /// Removes `node` and every edge touching it, one journaled edit at a
+    /// time. Returns the number of edges removed.
+    pub fn remove_node(&mut self, node: &G::Node) -> Result<usize, GraphError> {
+        ensure_node(&*self.graph, node)?;
+        let mut incident: Vec<EdgeId> = self.graph.out_edges(node).chain(self.graph.in_edges(node)).map(|e| e.id).collect();
+        incident.sort();
+        incident.dedup();
+        for id in &incident {
+            self.apply(Edit::DeleteEdge { id: *id })?;
+        }
+        self.apply(Edit::DeleteNode { node: node.clone() })?;
+        Ok(incident.len())
+    }
+
+    /// Keeps every edit made so far.
+    pub fn commit(mut self) {
+        self.journal.clear();
+    }
+
+    /// Reverts every edit made so far. Same as dropping the transaction.
+    pub fn rollback(self) {}
+
+    /// Commits, handing the journal to `History` for undo.
+    fn into_journal(mut self) -> Vec<EditOf<G>> {
+        std::mem::take(&mut self.journal)
+    }
+
+    /// Journals the creation of the endpoints `add_edge` is about to make
+    /// for `u` and `v`, if they are missing.
+    fn note_new_endpoints(&mut self, u: &G::Node, v: &G::Node) -> [Option<EditOf<G>>; 2] {
+        let new_u = !self.graph.contains_node(u);
+        let new_v = u != v && !self.graph.contains_node(v);
+        [new_u.then(|| Edit::DeleteNode { node: u.clone() }), new_v.then(|| Edit::DeleteNode { node: v.clone() })]
+    }
+
+    /// Journals a successful `add_edge` together with the endpoints it
+    /// created.
+    fn journal_added_edge(&mut self, created: [Option<EditOf<G>>; 2], id: EdgeId) {
+        self.journal.extend(created.into_iter().flatten());
+        self.journal.push(Edit::DeleteEdge { id });
+    }
+}
+
+impl<G: Editable> Drop for Transaction<'_, G> {
+    fn drop(&mut self) {
+        let journal = std::mem::take(&mut self.journal);
+        replay(self.graph, journal);
+    }
+}
+
+impl<N: Eq + Hash + Clone, NData: Clone, EData: Clone> Transaction<'_, Graph<N, NData, EData>> {
+    /// `Graph::add_edge` inside the transaction.
+    pub fn add_edge(&mut self, u: N, v: N) -> Result<EdgeId, GraphError> {
+        let created = self.note_new_endpoints(&u, &v);
+        let id = self.graph.add_edge(u, v)?;
+        self.journal_added_edge(created, id);
+        Ok(id)
+    }
+
+    /// `Graph::add_edge_with_data` inside the transaction.
+    pub fn add_edge_with_data(&mut self, u: N, v: N, data: EData) -> Result<EdgeId, GraphError> {
+        let created = self.note_new_endpoints(&u, &v);
+        let id = self.graph.add_edge_with_data(u, v, data)?;
+        self.journal_added_edge(created, id);
+        Ok(id)
+    }
+
+    /// `Graph::remove_edge` inside the transaction.
+    pub fn remove_edge(&mut self, u: &N, v: &N) -> Result<EdgeId, GraphError> {
+        if !self.graph.contains_node(u) || !self.graph.contains_node(v) {
+            return Err(GraphError::NodeNotFound);
+        }
+        let id = self.graph.edges_connecting(u, v).next().ok_or(GraphError::EdgeNotFound)?.id;
+        self.remove_edge_by_id(id)?;
+        Ok(id)
+    }
+}
+
+impl<N: Eq + Hash + Clone, W: Weight> Transaction<'_, WeightedGraph<N, W>> {
+    /// `WeightedGraph::add_edge` inside the transaction.
+    pub fn add_edge(&mut self, u: N, v: N, weight: W) -> Result<EdgeId, GraphError> {
+        let created = self.note_new_endpoints(&u, &v);
+        let id = self.graph.add_edge(u, v, weight)?;
+        self.journal_added_edge(created, id);
+        Ok(id)
+    }
+
+    /// `WeightedGraph::remove_edge` inside the transaction.
+    pub fn remove_edge(&mut self, u: &N, v: &N) -> Result<W, GraphError> {
+        if !self.graph.contains_node(u) || !self.graph.contains_node(v) {
+            return Err(GraphError::NodeNotFound);
+        }
+        let id = self.graph.edges_connecting(u, v).next().ok_or(GraphError::EdgeNotFound)?.id;
+        self.remove_edge_by_id(id).map(|e| e.weight)
+    }
+
+    /// `WeightedGraph::set_weight` inside the transaction.
+    pub fn set_weight(&mut self, id: EdgeId, weight: W) -> Result<W, GraphError> {
+        let old = self.graph.set_weight(id, weight)?;
+        self.journal.push(Edit::SetWeight { id, weight: old });
+        Ok(old)
+    }
+}
+
+/// Applies a journal of inverses newest first, returning the inverses of
+/// those, which replay the journal again. Panics if an inverse is rejected,
+/// which would mean the journal and the graph no longer agree.
+fn replay<G: Editable>(graph: &mut G, journal: Vec<EditOf<G>>) -> Vec<EditOf<G>> {
+    // the graph was only changed through the journaled edits, so every
+    // inverse still applies; skipping one would leave it half-reverted
+    journal.into_iter().rev().map(|edit| graph.apply_edit(edit).expect("journaled inverse must apply")).collect()
+}
+
+/// Owns a graph and keeps undo and redo stacks of the transactions run
+/// through `edit`, for interactive editors.
+pub struct History<G: Editable> {
+    graph: G,
+    undo: Vec<Vec<EditOf<G>>>,
+    redo: Vec<Vec<EditOf<G>>>,
+}
+
+impl<G: Editable> History<G> {
+    pub fn new(graph: G) -> Self {
+        History { graph, undo: Vec::new(), redo: Vec::new() }
+    }
+
+    pub fn graph(&self) -> &G {
+        &self.graph
+    }
+
+    /// Gives the graph back, dropping the history.
+    pub fn into_inner(self) -> G {
+        self.graph
+    }
+
+    /// Runs `f` as one transaction. If it succeeds the edits are kept as
+    /// one undo step and the redo stack is cleared; if it fails they are
+    /// rolled back and the error returned.
+    pub fn edit<T, F>(&mut self, f: F) -> Result<T, GraphError>
+    where
+        F: FnOnce(&mut Transaction<'_, G>) -> Result<T, GraphError>,
+    {
+        let mut tx = Transaction::new(&mut self.graph);
+        let value = f(&mut tx)?;
+        let journal = tx.into_journal();
+        if !journal.is_empty() {
+            self.undo.push(journal);
+            self.redo.clear();
+        }
+        Ok(value)
+    }
+
+    /// Reverts the latest edit step. Returns `false` if there is none.
+    pub fn undo(&mut self) -> bool {
+        let Some(journal) = self.undo.pop() else {
+            return false;
+        };
+        self.redo.push(replay(&mut self.graph, journal));
+        true
+    }
+
+    /// Re-applies the latest undone step. Returns `false` if there is none.
+    pub fn redo(&mut self) -> bool {
+        let Some(journal) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push(replay(&mut self.graph, journal));
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::policy::EdgePolicy;
+
+    fn sorted_edges<N: Ord + Clone, W: Clone>(edges: impl Iterator<Item = EdgeRef<N, W>>) -> Vec<(EdgeId, N, N)> {
+        let mut edges: Vec<_> = edges.map(|e| (e.id, e.source, e.target)).collect();
+        edges.sort();
+        edges
+    }
+
+    #[test]
+    fn test_rollback_restores_graph() {
+        let mut graph: Graph<i32, &str, u8> = Graph::attributed(false, EdgePolicy::SIMPLE);
+        graph.add_node_with_data(1, "one");
+        let a = graph.add_edge_with_data(1, 2, 7).unwrap();
+        graph.add_edge(2, 3).unwrap();
+        let before = sorted_edges(GraphBase::edges(&graph));
+
+        let mut tx = graph.begin();
+        tx.add_edge(3, 4).unwrap();
+        assert_eq!(tx.add_edge(2, 1), Err(GraphError::DuplicateEdge));
+        assert_eq!(tx.remove_node(&1), Ok(1));
+        assert_eq!(tx.remove_edge(&2, &9), Err(GraphError::NodeNotFound));
+        assert_eq!(tx.add_node(9), Ok(true));
+        assert_eq!(tx.add_node(9), Ok(false));
+        assert_eq!(tx.graph().node_count(), 4);
+        tx.rollback();
+
+        assert_eq!(sorted_edges(GraphBase::edges(&graph)), before);
+        assert_eq!(graph.node_count(), 3);
+        assert_eq!(graph.node_data(&1), Some(&"one"));
+        assert_eq!(graph.edge_data(a), Some(&7));
+        assert_eq!(graph.validate(), Ok(()));
+
+        assert_eq!(graph.apply_edit(Edit::DeleteNode { node: 1 }), Err(GraphError::NodeHasEdges));
+        assert_eq!(graph.node_data(&1), Some(&"one"));
+        assert_eq!(sorted_edges(GraphBase::edges(&graph)), before);
+    }
+
+    #[test]
+    fn test_commit_and_drop() {
+        let mut graph = WeightedGraph::new(true);
+        let ab = graph.add_edge("a", "b", 1).unwrap();
+        graph.enable_reverse_index();
+
+        let mut tx = graph.begin();
+        tx.add_edge("b", "c", 2).unwrap();
+        assert_eq!(tx.set_weight(ab, 5), Ok(1));
+        tx.commit();
+        assert_eq!(graph.weight(ab), Some(5));
+        assert_eq!(graph.edge_count(), 2);
+
+        {
+            let mut tx = graph.begin();
+            tx.remove_node(&"b").unwrap();
+            tx.set_weight(ab, 9).unwrap_err();
+        }
+        assert_eq!(graph.weight(ab), Some(5));
+        assert_eq!(graph.predecessors(&"c").count(), 1);
+        assert_eq!(graph.validate(), Ok(()));
+        assert_eq!(graph.apply_edit(Edit::DeleteNode { node: "c" }), Err(GraphError::NodeHasEdges));
+        assert_eq!(graph.edge_count(), 2);
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let mut history = History::new(Graph::new(true));
+        let first = history.edit(|tx| tx.add_edge(1, 2)).unwrap();
+        history.edit(|tx| {
+            tx.add_edge(2, 3)?;
+            tx.remove_edge_by_id(first)
+        }).unwrap();
+        assert_eq!(history.edit(|tx| {
+            tx.add_edge(3, 4)?;
+            tx.remove_node(&7)
+        }), Err(GraphError::NodeNotFound));
+        assert_eq!(history.graph().node_count(), 3);
+
+        assert!(history.undo());
+        assert!(history.graph().contains_edge(first));
+        assert!(!history.graph().contains_node(&3));
+        assert!(history.undo());
+        assert_eq!(history.graph().node_count(), 0);
+        assert!(!history.undo());
+
+        assert!(history.redo());
+        assert!(history.redo());
+        assert!(!history.redo());
+        assert_eq!(sorted_edges(GraphBase::edges(history.graph())).len(), 1);
+        assert!(history.undo());
+        assert_eq!(history.edit(|tx| tx.add_node(5)), Ok(true));
+        assert!(!history.can_redo());
+        assert_eq!(history.into_inner().edge(first).map(|e| (*e.source, *e.target)), Some((1, 2)));
+    }
+}
@@ -0,0 +1,375 @@
+use std::collections::HashSet;
+
+use crate::core::edge::EdgeRef;
+use crate::core::traits::{EdgeOf, GraphBase};
+
+/// Zero-copy adapters, available on every `GraphBase`. The returned views
+/// implement `GraphBase` themselves, so traversals and other views accept
+/// them like any graph.
+pub trait ViewExt: GraphBase {
+    /// The graph with every edge pointing the other way.
+    fn reversed(&self) -> Reversed<'_, Self> {
+        Reversed::new(self)
+    }
+
+    /// The graph with edge direction ignored.
+    fn as_undirected(&self) -> Undirected<'_, Self> {
+        Undirected::new(self)
+    }
+
+    /// The nodes for which `keep` returns `true` and the edges between them.
+    fn filter_nodes<F: Fn(&Self::Node) -> bool>(&self, keep: F) -> NodeFiltered<'_, Self, F> {
+        NodeFiltered::new(self, keep)
+    }
+
+    /// Every node, but only the edges for which `keep` returns `true`.
+    fn filter_edges<F: Fn(&EdgeOf<Self>) -> bool>(&self, keep: F) -> EdgeFiltered<'_, Self, F> {
+        EdgeFiltered::new(self, keep)
+    }
+
+    /// The subgraph induced by `nodes`: those nodes and every edge between
+    /// two of them.
+    fn induced<'a>(&'a self, nodes: &'a HashSet<Self::Node>) -> NodeFiltered<'a, Self, impl Fn(&Self::Node) -> bool + 'a> {
+        NodeFiltered::new(self, move |n: &Self::Node| nodes.contains(n))
+    }
+}
+
+impl<G: GraphBase + ?Sized> ViewExt for G {}
+
+/// Borrowed view of a graph with every edge pointing the other way.
+///
+/// Neighbors of the view are the predecessors of the underlying graph, so
+/// traversals over it walk against edge direction without copying anything.
+/// Keep a reverse index enabled on the underlying graph to make this cheap.
+#[derive(Debug)]
+pub struct Reversed<'a, G: ?Sized> {
+    graph: &'a G,
+}
+
+impl<'a, G: ?Sized> Reversed<'a, G> {
+    pub fn new(graph: &'a G) -> Self {
+        Reversed { graph }
+    }
+}
+
+impl<G: ?Sized> Clone for Reversed<'_, G> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<G: ?Sized> Copy for Reversed<'_, G> {}
+
+impl<'a, G: GraphBase + ?Sized> GraphBase for Reversed<'a, G> {
+    type Node = G::Node;
+    type Weight = G::Weight;
+
+    fn is_directed(&self) -> bool {
+        self.graph.is_directed()
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = G::Node> + '_ {
+        self.graph.nodes()
+    }
+
+    fn neighbors<'b>(&'b self, node: &G::Node) -> impl Iterator<Item = G::Node> + use<'a, 'b, G> {
+        self.graph.predecessors(node)
+    }
+
+    fn out_edges<'b>(&'b self, node: &G::Node) -> impl Iterator<Item = EdgeRef<G::Node, G::Weight>> + use<'a, 'b, G> {
+        self.graph.in_edges(node).map(EdgeRef::reversed)
+    }
+
+    fn in_edges<'b>(&'b self, node: &G::Node) -> impl Iterator<Item = EdgeRef<G::Node, G::Weight>> + use<'a, 'b, G> {
+        self.graph.out_edges(node).map(EdgeRef::reversed)
+    }
+
+    fn predecessors<'b>(&'b self, node: &G::Node) -> impl Iterator<Item = G::Node> + use<'a, 'b, G> {
+        self.graph.neighbors(node)
+    }
+
+    fn contains_node(&self, node: &G::Node) -> bool {
+        self.graph.contains_node(node)
+    }
+
+    fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    fn node_index(&self, node: &G::Node) -> Option<usize> {
+        self.graph.node_index(node)
+    }
+
+    fn node_bound(&self) -> Option<usize> {
+        self.graph.node_bound()
+    }
+}
+
+/// Borrowed view of a graph with edge direction ignored.
+///
+/// Every node's neighbors are its out-neighbors followed by its
+/// predecessors, so a directed edge can be crossed both ways and a directed
+/// self-loop is seen twice, like an undirected one. Views of graphs that
+/// are already undirected just forward.
+#[derive(Debug)]
+pub struct Undirected<'a, G: ?Sized> {
+    graph: &'a G,
+}
+
+impl<'a, G: ?Sized> Undirected<'a, G> {
+    pub fn new(graph: &'a G) -> Self {
+        Undirected { graph }
+    }
+}
+
+impl<G: ?Sized> Clone for Undirected<'_, G> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<G: ?Sized> Copy for Undirected<'_, G> {}
+
+impl<'a, G: GraphBase + ?Sized> GraphBase for Undirected<'a, G> {
+    type Node = G::Node;
+    type Weight = G::Weight;
+
+    fn is_directed(&self) -> bool {
+        false
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = G::Node> + '_ {
+        self.graph.nodes()
+    }
+
+    fn neighbors<'b>(&'b self, node: &G::Node) -> impl Iterator<Item = G::Node> + use<'a, 'b, G> {
+        self.out_edges(node).map(|e| e.target)
+    }
+
+    fn out_edges<'b>(&'b self, node: &G::Node) -> impl Iterator<Item = EdgeRef<G::Node, G::Weight>> + use<'a, 'b, G> {
+        let incoming = self.graph.is_directed().then(|| self.graph.in_edges(node).map(EdgeRef::reversed));
+        self.graph.out_edges(node).chain(incoming.into_iter().flatten())
+    }
+
+    fn contains_node(&self, node: &G::Node) -> bool {
+        self.graph.contains_node(node)
+    }
+
+    fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    fn edge_count(&self) -> usize {
+        self.graph.edge_count()
+    }
+
+    fn node_index(&self, node: &G::Node) -> Option<usize> {
+        self.graph.node_index(node)
+    }
+
+    fn node_bound(&self) -> Option<usize> {
+        self.graph.node_bound()
+    }
+}
+
+/// Borrowed view of the nodes for which a predicate holds, with every edge
+/// whose endpoints both pass. `induced` builds one from a node set.
+pub struct NodeFiltered<'a, G: ?Sized, F> {
+    graph: &'a G,
+    keep: F,
+}
+
+impl<'a, G: ?Sized, F> NodeFiltered<'a, G, F> {
+    pub fn new(graph: &'a G, keep: F) -> Self {
+        NodeFiltered { graph, keep }
+    }
+}
+
+impl<G: ?Sized, F: Clone> Clone for NodeFiltered<'_, G, F> {
+    fn clone(&self) -> Self {
+        NodeFiltered { graph: self.graph, keep: self.keep.clone() }
+    }
+}
+
+impl<'a, G, F> GraphBase for NodeFiltered<'a, G, F>
+where
+    G: GraphBase + ?Sized,
+    F: Fn(&G::Node) -> bool,
+{
+    type Node = G::Node;
+    type Weight = G::Weight;
+
+    fn is_directed(&self) -> bool {
+        self.graph.is_directed()
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = G::Node> + '_ {
+        self.graph.nodes().filter(|n| (self.keep)(n))
+    }
+
+    fn neighbors<'b>(&'b self, node: &G::Node) -> impl Iterator<Item = G::Node> + use<'a, 'b, G, F> {
+        self.out_edges(node).map(|e| e.target)
+    }
+
+    fn out_edges<'b>(&'b self, node: &G::Node) -> impl Iterator<Item = EdgeRef<G::Node, G::Weight>> + use<'a, 'b, G, F> {
+        let kept = (self.keep)(node);
+        self.graph.out_edges(node).filter(move |e| kept && (self.keep)(&e.target))
+    }
+
+    fn in_edges<'b>(&'b self, node: &G::Node) -> impl Iterator<Item = EdgeRef<G::Node, G::Weight>> + use<'a, 'b, G, F> {
+        let kept = (self.keep)(node);
+        self.graph.in_edges(node).filter(move |e| kept && (self.keep)(&e.source))
+    }
+
+    fn contains_node(&self, node: &G::Node) -> bool {
+        (self.keep)(node) && self.graph.contains_node(node)
+    }
+
+    fn node_index(&self, node: &G::Node) -> Option<usize> {
+        self.graph.node_index(node)
+    }
+
+    fn node_bound(&self) -> Option<usize> {
+        self.graph.node_bound()
+    }
+}
+
+/// Borrowed view with every node but only the edges for which a predicate
+/// holds. In undirected graphs the predicate may see an edge in either
+/// orientation, so it should not depend on which endpoint is `source`.
+pub struct EdgeFiltered<'a, G: ?Sized, F> {
+    graph: &'a G,
+    keep: F,
+}
+
+impl<'a, G: ?Sized, F> EdgeFiltered<'a, G, F> {
+    pub fn new(graph: &'a G, keep: F) -> Self {
+        EdgeFiltered { graph, keep }
+    }
+}
+
+impl<G: ?Sized, F: Clone> Clone for EdgeFiltered<'_, G, F> {
+    fn clone(&self) -> Self {
+        EdgeFiltered { graph: self.graph, keep: self.keep.clone() }
+    }
+}
+
+impl<'a, G, F> GraphBase for EdgeFiltered<'a, G, F>
+where
+    G: GraphBase + ?Sized,
+    F: Fn(&EdgeOf<G>) -> bool,
+{
+    type Node = G::Node;
+    type Weight = G::Weight;
+
+    fn is_directed(&self) -> bool {
+        self.graph.is_directed()
+    }
+
+    fn nodes(&self) -> impl Iterator<Item = G::Node> + '_ {
+        self.graph.nodes()
+    }
+
+    fn neighbors<'b>(&'b self, node: &G::Node) -> impl Iterator<Item = G::Node> + use<'a, 'b, G, F> {
+        self.out_edges(node).map(|e| e.target)
+    }
+
+    fn out_edges<'b>(&'b self, node: &G::Node) -> impl Iterator<Item = EdgeRef<G::Node, G::Weight>> + use<'a, 'b, G, F> {
+        self.graph.out_edges(node).filter(|e| (self.keep)(e))
+    }
+
+    fn in_edges<'b>(&'b self, node: &G::Node) -> impl Iterator<Item = EdgeRef<G::Node, G::Weight>> + use<'a, 'b, G, F> {
+        self.graph.in_edges(node).filter(|e| (self.keep)(e))
+    }
+
+    fn contains_node(&self, node: &G::Node) -> bool {
+        self.graph.contains_node(node)
+    }
+
+    fn node_count(&self) -> usize {
+        self.graph.node_count()
+    }
+
+    fn node_index(&self, node: &G::Node) -> Option<usize> {
+        self.graph.node_index(node)
+    }
+
+    fn node_bound(&self) -> Option<usize> {
+        self.graph.node_bound()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::algos::bfs::BfsExt;
+    use crate::algos::dfs::DfsExt;
+    use crate::core::error::GraphError;
+    use crate::core::graph::Graph;
+    use crate::core::weightedgraph::WeightedGraph;
+
+    fn chain() -> Graph {
+        // 1 -> 2 -> 3 -> 4, 5 -> 3
+        let mut graph = Graph::new(true);
+        graph.add_edge(1, 2).unwrap();
+        graph.add_edge(2, 3).unwrap();
+        graph.add_edge(3, 4).unwrap();
+        graph.add_edge(5, 3).unwrap();
+        graph
+    }
+
+    #[test]
+    fn test_reversed_and_undirected() {
+        let graph = chain();
+
+        assert_eq!(graph.reversed().bfs(4).unwrap().len(), 5);
+        assert_eq!(graph.bfs(5).unwrap(), vec![5, 3, 4]);
+
+        let undirected = graph.as_undirected();
+        assert!(!undirected.is_directed());
+        assert_eq!(undirected.bfs(5).unwrap().len(), 5);
+        assert_eq!(undirected.edge_count(), 4);
+        assert_eq!(undirected.edges().count(), 4);
+        let mut around_3: Vec<_> = undirected.neighbors(&3).collect();
+        around_3.sort();
+        assert_eq!(around_3, vec![2, 4, 5]);
+    }
+
+    #[test]
+    fn test_node_filter_and_induced() {
+        let graph = chain();
+
+        let without_3 = graph.filter_nodes(|&n| n != 3);
+        assert_eq!(without_3.bfs(1).unwrap(), vec![1, 2]);
+        assert_eq!(without_3.node_count(), 4);
+        assert_eq!(without_3.edge_count(), 1);
+        assert_eq!(without_3.bfs(3), Err(GraphError::NodeNotFound));
+
+        let keep = HashSet::from([2, 3, 5]);
+        let induced = graph.induced(&keep);
+        assert_eq!(induced.edge_count(), 2);
+        assert_eq!(induced.predecessors(&3).count(), 2);
+        assert_eq!(induced.reversed().dfs(3).unwrap().len(), 3);
+    }
+
+    #[test]
+    fn test_edge_filter() {
+        let mut graph: WeightedGraph<&str, u32> = WeightedGraph::new(false);
+        graph.add_edge("a", "b", 1).unwrap();
+        graph.add_edge("b", "c", 10).unwrap();
+        graph.add_edge("a", "d", 2).unwrap();
+        graph.add_edge("d", "c", 3).unwrap();
+
+        let cheap = graph.filter_edges(|e| e.weight < 5);
+        assert_eq!(cheap.shortest_path_bfs("b", "c").unwrap(), Some(vec!["b", "a", "d", "c"]));
+        assert_eq!(cheap.edge_count(), 3);
+        assert_eq!(cheap.node_count(), 4);
+        // views compose
+        let cheap_without_d = cheap.filter_nodes(|n| *n != "d");
+        assert_eq!(cheap_without_d.shortest_path_bfs("b", "c").unwrap(), None);
+    }
+}
@@ -0,0 +1,126 @@
+use std::fmt::Debug;
+use std::ops::Add;
+
+/// Edge weight used by `WeightedGraph`.
+///
+/// `zero` and `checked_add` are required, so every implementor decides how
+/// overflow is detected. The remaining methods have defaults; the primitive
+/// implementations override them where the type offers something better.
+pub trait Weight: Copy + PartialOrd + Add<Output = Self> + Debug {
+    /// The additive identity, i.e. the length of an empty path.
+    fn zero() -> Self;
+
+    /// Whether the value can be used as a weight at all. The default rejects
+    /// values that don't compare equal to themselves, such as NaN.
+    fn is_valid(self) -> bool {
+        self.partial_cmp(&self).is_some()
+    }
+
+    /// A value larger than any reachable distance, if the type has one.
+    fn infinity() -> Option<Self> {
+        None
+    }
+
+    /// Addition that reports overflow instead of wrapping or panicking.
+    fn checked_add(self, other: Self) -> Option<Self>;
+
+    /// Addition that clamps toward the bound in the direction of the
+    /// overflow, e.g. `MAX` for a positive and `MIN` for a negative integer
+    /// overflow.
+    ///
+    /// The default clamps to `infinity()` and, for types without one, falls
+    /// back to plain addition; override it when overflow can go both ways.
+    fn saturating_add(self, other: Self) -> Self {
+        match self.checked_add(other) {
+            Some(sum) => sum,
+            None => Self::infinity().unwrap_or(self + other),
+        }
+    }
+}
+
+macro_rules! impl_int_weight {
+    ($($t:ty),*) => {$(
+        impl Weight for $t {
+            fn zero() -> Self {
+                0
+            }
+
+            fn infinity() -> Option<Self> {
+                Some(<$t>::MAX)
+            }
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                <$t>::checked_add(self, other)
+            }
+
+            fn saturating_add(self, other: Self) -> Self {
+                <$t>::saturating_add(self, other)
+            }
+        }
+    )*};
+}
+
+macro_rules! impl_float_weight {
+    ($($t:ty),*) => {$(
+        impl Weight for $t {
+            fn zero() -> Self {
+                0.0
+            }
+
+            fn infinity() -> Option<Self> {
+                Some(<$t>::INFINITY)
+            }
+
+            fn checked_add(self, other: Self) -> Option<Self> {
+                let sum = self + other;
+                if sum.is_finite() || !self.is_finite() || !other.is_finite() {
+                    Some(sum)
+                } else {
+                    None
+                }
+            }
+
+            fn saturating_add(self, other: Self) -> Self {
+                self + other
+            }
+        }
+    )*};
+}
+
+impl_int_weight!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+impl_float_weight!(f32, f64);
+
+/// Sums weights with `checked_add`, returning `None` on the first overflow.
+pub fn checked_sum<W: Weight, I: IntoIterator<Item = W>>(weights: I) -> Option<W> {
+    weights
+        .into_iter()
+        .try_fold(W::zero(), |acc, w| acc.checked_add(w))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_int_weights_saturate() {
+        assert_eq!(Weight::checked_add(u8::MAX, 1), None);
+        assert_eq!(Weight::saturating_add(250u8, 10), u8::MAX);
+        assert_eq!(Weight::saturating_add(i32::MIN, -1), i32::MIN);
+        assert_eq!(<u64 as Weight>::infinity(), Some(u64::MAX));
+    }
+
+    #[test]
+    fn test_float_weights() {
+        assert_eq!(<f64 as Weight>::zero(), 0.0);
+        assert_eq!(Weight::checked_add(f64::MAX, f64::MAX), None);
+        assert_eq!(Weight::checked_add(f64::INFINITY, 1.0), Some(f64::INFINITY));
+        assert_eq!(Weight::saturating_add(f32::MAX, f32::MAX), f32::INFINITY);
+    }
+
+    #[test]
+    fn test_checked_sum() {
+        assert_eq!(checked_sum(vec![1u32, 2, 3]), Some(6));
+        assert_eq!(checked_sum(vec![200u8, 100]), None);
+        assert_eq!(checked_sum(Vec::<i64>::new()), Some(0));
+    }
+}
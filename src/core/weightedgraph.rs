@@ -1,26 +1,875 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use crate::core::changelog::{Change, ChangeLog};
+use crate::core::edge::{EdgeId, EdgeRef};
+use crate::core::error::{GraphError, Violation};
+use crate::core::graph::{remove_first, Graph};
+use crate::core::integrity::{self, Storage};
+use crate::core::policy::EdgePolicy;
+use crate::core::snapshot::{SharedAdjacency, WeightedSnapshot};
+use crate::core::transaction::Transaction;
+use crate::core::weight::{checked_sum, Weight};
+
+/// Per-node list of `(neighbor, weight, edge id)` entries.
+pub type WeightedAdjacency<N, W> = HashMap<N, Vec<(N, W, EdgeId)>>;
 
 #[derive(Debug, Clone)]
-pub struct WeightedGraph {
-    pub ajd_list: HashMap<i32, Vec<(i32,i32)>>,
-    pub is_directed: bool,
+pub struct WeightedGraph<N = i32, W = i32> {
+    /// Out-neighbors of each node with the edge weight and id. Undirected
+    /// edges appear in both endpoints' lists under the same id.
+    ajd_list: SharedAdjacency<N, (N, W, EdgeId)>,
+    is_directed: bool,
+    policy: EdgePolicy,
+    /// Endpoints and weight of every edge, in the orientation it was added.
+    edge_table: HashMap<EdgeId, (N, N, W)>,
+    next_edge_id: usize,
+    /// Incoming edges of a directed graph, kept only once
+    /// `enable_reverse_index` has been called.
+    rev_list: Option<SharedAdjacency<N, (N, W, EdgeId)>>,
+    /// Version counter and, once enabled, the record of structural changes
+    /// and weight updates.
+    changes: ChangeLog<N, W>,
 }
 
-impl WeightedGraph {
+impl<N: Eq + Hash + Clone, W: Weight> WeightedGraph<N, W> {
     pub fn new(is_directed: bool) -> Self{
         WeightedGraph{
-            ajd_list: HashMap::new(),
+            ajd_list: SharedAdjacency::default(),
             is_directed,
+            policy: EdgePolicy::default(),
+            edge_table: HashMap::new(),
+            next_edge_id: 0,
+            rev_list: None,
+            changes: ChangeLog::new(),
+        }
+
+    }
+
+    /// Creates an empty graph whose `add_edge` enforces `policy`.
+    pub fn with_policy(is_directed: bool, policy: EdgePolicy) -> Self {
+        WeightedGraph {
+            policy,
+            ..WeightedGraph::new(is_directed)
+        }
+    }
+
+    /// Builds a graph from `(u, v, weight)` edge tuples, creating nodes as
+    /// needed. Fails on the first invalid weight.
+    pub fn from_edges<I: IntoIterator<Item = (N, N, W)>>(is_directed: bool, edges: I) -> Result<Self, GraphError> {
+        let mut graph = WeightedGraph::new(is_directed);
+        for (u, v, w) in edges {
+            graph.add_edge(u, v, w)?;
+        }
+        Ok(graph)
+    }
+
+    /// Builds a graph from a plain weighted adjacency map, e.g. one
+    /// deserialized or produced by older code, storing it as given without
+    /// checking any invariant. Every `u -> v` entry becomes an edge; in an
+    /// undirected graph it is paired with a `v -> u` entry of the same
+    /// weight into one edge when there is one left to pair with. Run
+    /// `validate` or `repair` on the result.
+    pub fn from_adjacency_unchecked(is_directed: bool, policy: EdgePolicy, adjacency: HashMap<N, Vec<(N, W)>>) -> Self {
+        let mut graph = WeightedGraph::with_policy(is_directed, policy);
+        let lists = integrity::assign_ids(is_directed, adjacency, |(v, _)| v, |(_, a), (_, b)| a == b, |u, &(ref v, w)| {
+            let id = EdgeId(graph.next_edge_id);
+            graph.next_edge_id += 1;
+            graph.edge_table.insert(id, (u.clone(), v.clone(), w));
+            id
+        });
+        for (u, out) in lists {
+            graph.ajd_list.insert(u, out.into_iter().map(|((v, w), id)| (v, w, id)).collect());
+        }
+        graph
+    }
+
+    pub fn policy(&self) -> EdgePolicy {
+        self.policy
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.is_directed
+    }
+
+    /// Starts maintaining an index of incoming edges so `predecessors` and
+    /// `in_degree` no longer scan the whole graph. Every later mutation keeps
+    /// it current. Undirected graphs don't need one and are left unchanged.
+    pub fn enable_reverse_index(&mut self) {
+        if !self.is_directed || self.rev_list.is_some() {
+            return;
         }
+        let mut rev = SharedAdjacency::default();
+        for (u, out) in self.ajd_list.iter() {
+            for (v, w, id) in out {
+                rev.entry_or_default(v.clone()).push((u.clone(), *w, *id));
+            }
+        }
+        self.rev_list = Some(rev);
+    }
+
+    /// Drops the incoming-edge index, freeing its memory.
+    pub fn disable_reverse_index(&mut self) {
+        self.rev_list = None;
+    }
+
+    pub fn has_reverse_index(&self) -> bool {
+        self.rev_list.is_some()
+    }
+
+    /// Starts recording node and edge insertions and removals and weight
+    /// updates, so caches derived from the graph can catch up through
+    /// `change_log().since` instead of recomputing.
+    pub fn enable_change_log(&mut self) {
+        self.changes.start_recording();
+    }
+
+    /// Stops recording and drops the changes not drained yet. The version
+    /// keeps counting.
+    pub fn disable_change_log(&mut self) {
+        self.changes.stop_recording();
+    }
 
+    /// Number of changes made to the graph so far.
+    pub fn version(&self) -> u64 {
+        self.changes.version()
     }
 
-    pub fn add_edge(&mut self, u: i32, v: i32, weight: i32) {
-        self.ajd_list.entry(u).or_insert(Vec::new()).push((v,weight));
+    /// The change log, for reading deltas with `since` or subscribing.
+    pub fn change_log(&self) -> &ChangeLog<N, W> {
+        &self.changes
+    }
+
+    /// The change log, for draining deltas or managing observers.
+    pub fn change_log_mut(&mut self) -> &mut ChangeLog<N, W> {
+        &mut self.changes
+    }
+
+    /// Takes an O(1) read-only snapshot of the graph's structure that later
+    /// mutations of the graph do not affect. While it is alive, each write
+    /// copies the O(log V) path to the node it touches plus that node's
+    /// adjacency list, never the whole graph. See `WeightedSnapshot`.
+    pub fn snapshot(&self) -> WeightedSnapshot<N, W> {
+        WeightedSnapshot {
+            adj: self.ajd_list.clone(),
+            rev: self.rev_list.clone(),
+            is_directed: self.is_directed,
+            edge_count: self.edge_table.len(),
+            version: self.version(),
+        }
+    }
+
+    /// Starts a transaction: edits made through it are rolled back unless
+    /// it is committed.
+    pub fn begin(&mut self) -> Transaction<'_, Self> {
+        Transaction::new(self)
+    }
+
+    /// Incoming adjacency of `node` when it can be read without scanning:
+    /// the neighbor list of an undirected graph or the reverse index of a
+    /// directed one. `None` means the caller has to scan.
+    pub(crate) fn incoming(&self, node: &N) -> Option<&[(N, W, EdgeId)]> {
+        let map = if self.is_directed { self.rev_list.as_ref()? } else { &self.ajd_list };
+        Some(map.get(node).map_or(&[], Vec::as_slice))
+    }
+
+    /// Iterates over the nodes with an edge into `node`, with that edge's
+    /// weight. Undirected graphs return the neighbors; directed graphs
+    /// without a reverse index scan every adjacency list.
+    pub fn predecessors<'a>(&'a self, node: &'a N) -> Box<dyn Iterator<Item = (&'a N, &'a W)> + 'a> {
+        match self.incoming(node) {
+            Some(incoming) => Box::new(incoming.iter().map(|(u, w, _)| (u, w))),
+            None => Box::new(self.ajd_list.iter().flat_map(move |(u, out)| {
+                out.iter().filter(move |(v, _, _)| v == node).map(move |(_, w, _)| (u, w))
+            })),
+        }
+    }
+
+    /// Adds the edge `u -> v` (and `v -> u` if undirected), creating missing
+    /// nodes. Fails if the weight is invalid or the graph's `EdgePolicy`
+    /// rejects the edge.
+    pub fn add_edge(&mut self, u: N, v: N, weight: W) -> Result<EdgeId, GraphError> {
+        if !weight.is_valid() {
+            return Err(GraphError::InvalidWeight);
+        }
+        self.policy.check(&u, &v, || self.has_edge(&u, &v))?;
+        Ok(self.insert_edge(u, v, weight))
+    }
+
+    /// `add_edge` without the policy check, for callers that already know
+    /// the edge is acceptable.
+    pub(crate) fn insert_edge(&mut self, u: N, v: N, weight: W) -> EdgeId {
+        let id = EdgeId(self.next_edge_id);
+        self.insert_edge_with_id(u, v, weight, id);
+        id
+    }
+
+    /// Stores an edge under an id chosen by the caller, for conversions
+    /// that keep the source graph's ids. `id` must not be in use.
+    pub(crate) fn insert_edge_with_id(&mut self, u: N, v: N, weight: W, id: EdgeId) {
+        self.add_node(u.clone());
+        self.add_node(v.clone());
+        self.next_edge_id = self.next_edge_id.max(id.0 + 1);
+        self.edge_table.insert(id, (u.clone(), v.clone(), weight));
+        self.changes.record(|| Change::EdgeAdded(EdgeRef { source: u.clone(), target: v.clone(), weight, id }));
+
+        self.ajd_list.entry_or_default(u.clone()).push((v.clone(),weight,id));
         if !self.is_directed{
-            self.ajd_list.entry(v).or_insert(Vec::new()).push((u,weight));
+            self.ajd_list.entry_or_default(v).push((u,weight,id));
+
+        } else if let Some(rev) = &mut self.rev_list {
+            rev.entry_or_default(v).push((u, weight, id));
+        }
+    }
+
+    /// Adds an isolated node. Returns `false` if it was already present.
+    pub fn add_node(&mut self, node: N) -> bool {
+        if !self.ajd_list.add_key(node.clone()) {
+            return false;
+        }
+        self.changes.record(|| Change::NodeAdded(node));
+        true
+    }
+
+    pub fn contains_node(&self, node: &N) -> bool {
+        self.ajd_list.contains_key(node)
+    }
+
+    /// Whether at least one `u -> v` edge exists.
+    pub fn has_edge(&self, u: &N, v: &N) -> bool {
+        self.ajd_list.get(u).is_some_and(|out| out.iter().any(|(x, _, _)| x == v))
+    }
+
+    pub fn contains_edge(&self, id: EdgeId) -> bool {
+        self.edge_table.contains_key(&id)
+    }
+
+    /// Looks up an edge by id.
+    pub fn edge(&self, id: EdgeId) -> Option<EdgeRef<&N, W>> {
+        let (u, v, w) = self.edge_table.get(&id)?;
+        Some(EdgeRef { source: u, target: v, weight: *w, id })
+    }
+
+    /// Weight of the edge with the given id.
+    pub fn weight(&self, id: EdgeId) -> Option<W> {
+        self.edge_table.get(&id).map(|(_, _, w)| *w)
+    }
+
+    /// Replaces the weight of one edge, including its mirror entry and the
+    /// reverse index. Returns the previous weight.
+    pub fn set_weight(&mut self, id: EdgeId, weight: W) -> Result<W, GraphError> {
+        if !weight.is_valid() {
+            return Err(GraphError::InvalidWeight);
+        }
+        let (u, v, w) = self.edge_table.get_mut(&id).ok_or(GraphError::EdgeNotFound)?;
+        let old = std::mem::replace(w, weight);
+        set_entry_weight(self.ajd_list.get_mut(u), id, weight);
+        let mirror = if self.is_directed {
+            self.rev_list.as_mut().and_then(|rev| rev.get_mut(v))
+        } else {
+            self.ajd_list.get_mut(v)
+        };
+        set_entry_weight(mirror, id, weight);
+        self.changes.record(|| Change::WeightChanged { id, old, new: weight });
+        Ok(old)
+    }
+
+    /// Every `u -> v` edge; more than one if parallel edges are allowed.
+    pub fn edges_connecting<'a>(&'a self, u: &'a N, v: &'a N) -> impl Iterator<Item = EdgeRef<&'a N, W>> {
+        self.out_edges(u).filter(move |e| e.target == v)
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.ajd_list.len()
+    }
+
+    /// Number of edges, counting each undirected edge once.
+    pub fn edge_count(&self) -> usize {
+        self.edge_table.len()
+    }
+
+    /// Number of edges leaving `node`. In an undirected graph this is the
+    /// degree, with a self-loop counted twice.
+    pub fn out_degree(&self, node: &N) -> Result<usize, GraphError> {
+        self.ajd_list.get(node).map(Vec::len).ok_or(GraphError::NodeNotFound)
+    }
+
+    /// Number of edges entering `node`. Equal to `out_degree` for undirected
+    /// graphs; directed graphs without a reverse index scan every adjacency
+    /// list.
+    pub fn in_degree(&self, node: &N) -> Result<usize, GraphError> {
+        if !self.contains_node(node) {
+            return Err(GraphError::NodeNotFound);
+        }
+        Ok(self.predecessors(node).count())
+    }
+
+    /// Total degree: `in_degree + out_degree` for directed graphs, the
+    /// number of incident edge ends for undirected ones.
+    pub fn degree(&self, node: &N) -> Result<usize, GraphError> {
+        if self.is_directed {
+            Ok(self.in_degree(node)? + self.out_degree(node)?)
+        } else {
+            self.out_degree(node)
+        }
+    }
+
+    /// Iterates over every node, in no particular order.
+    pub fn nodes(&self) -> impl Iterator<Item = &N> {
+        self.ajd_list.keys()
+    }
+
+    /// Iterates over the out-neighbors of `node` with the connecting edge's
+    /// weight; empty if the node is unknown.
+    pub fn neighbors<'a>(&'a self, node: &N) -> impl Iterator<Item = (&'a N, &'a W)> + use<'a, N, W> {
+        self.ajd_list.get(node).into_iter().flatten().map(|(v, w, _)| (v, w))
+    }
+
+    /// Iterates over the edges leaving `node`; empty if it is unknown.
+    pub fn out_edges<'a>(&'a self, node: &N) -> impl Iterator<Item = EdgeRef<&'a N, W>> + use<'a, N, W> {
+        self.ajd_list.get_key_value(node).into_iter().flat_map(|(u, out)| {
+            out.iter().map(move |(v, w, id)| EdgeRef { source: u, target: v, weight: *w, id: *id })
+        })
+    }
+
+    /// Iterates over every edge, in the orientation it was added, reporting
+    /// each undirected edge once.
+    pub fn edges(&self) -> impl Iterator<Item = EdgeRef<&N, W>> {
+        self.edge_table.iter().map(|(id, (u, v, w))| EdgeRef { source: u, target: v, weight: *w, id: *id })
+    }
+
+    /// Removes one `u -> v` edge, and its mirror entry if the graph is
+    /// undirected. Returns the weight of the removed edge.
+    pub fn remove_edge(&mut self, u: &N, v: &N) -> Result<W, GraphError> {
+        if !self.contains_node(u) || !self.contains_node(v) {
+            return Err(GraphError::NodeNotFound);
+        }
+        let (_, _, id) = *self.ajd_list.get(u).into_iter().flatten().find(|(x, _, _)| x == v).ok_or(GraphError::EdgeNotFound)?;
+        self.remove_edge_by_id(id).map(|e| e.weight)
+    }
+
+    /// Removes the edge with the given id from every list it is stored in,
+    /// returning it.
+    pub fn remove_edge_by_id(&mut self, id: EdgeId) -> Result<EdgeRef<N, W>, GraphError> {
+        let (u, v, weight) = self.edge_table.remove(&id).ok_or(GraphError::EdgeNotFound)?;
+        remove_first(self.ajd_list.get_mut(&u), |(_, _, x)| *x == id);
+        if !self.is_directed {
+            remove_first(self.ajd_list.get_mut(&v), |(_, _, x)| *x == id);
+        } else if let Some(rev) = &mut self.rev_list {
+            remove_first(rev.get_mut(&v), |(_, _, x)| *x == id);
+        }
+        let removed = EdgeRef { source: u, target: v, weight, id };
+        self.changes.record(|| Change::EdgeRemoved(removed.clone()));
+        Ok(removed)
+    }
+
+    /// Removes `node` together with every edge touching it, in both
+    /// directions. Returns the number of edges removed.
+    pub fn remove_node(&mut self, node: &N) -> Result<usize, GraphError> {
+        let out = self.ajd_list.get(node).ok_or(GraphError::NodeNotFound)?;
+        let mut incident: HashSet<EdgeId> = out.iter().map(|(_, _, id)| *id).collect();
+        incident.extend(self.in_edge_ids(node));
+        for id in &incident {
+            let _ = self.remove_edge_by_id(*id);
+        }
+        self.ajd_list.remove(node);
+        if let Some(rev) = &mut self.rev_list {
+            rev.remove(node);
+        }
+        self.changes.record(|| Change::NodeRemoved(node.clone()));
+        Ok(incident.len())
+    }
+
+    fn in_edge_ids(&self, node: &N) -> Vec<EdgeId> {
+        match self.incoming(node) {
+            Some(incoming) => incoming.iter().map(|(_, _, id)| *id).collect(),
+            None => self.ajd_list.values().flatten().filter(|(v, _, _)| v == node).map(|(_, _, id)| *id).collect(),
+        }
+    }
+
+    /// Removes every edge but keeps all nodes. Returns the number of edges
+    /// removed.
+    pub fn clear_edges(&mut self) -> usize {
+        let removed = self.edge_table.len();
+        if self.changes.is_observed() {
+            let edges: Vec<EdgeRef<N, W>> = self.edges_in_id_order().into_iter().map(EdgeRef::cloned).collect();
+            for e in edges {
+                self.changes.record(|| Change::EdgeRemoved(e));
+            }
+        } else {
+            self.changes.skip(removed);
+        }
+        self.edge_table.clear();
+        self.ajd_list.clear_lists();
+        if let Some(rev) = &mut self.rev_list {
+            rev.clear();
+        }
+        removed
+    }
+
+    /// Keeps only the nodes for which `keep` returns `true`, dropping the
+    /// others along with their incident edges. Returns the number of nodes
+    /// removed.
+    pub fn retain_nodes<F: FnMut(&N) -> bool>(&mut self, mut keep: F) -> usize {
+        let doomed: Vec<N> = self.ajd_list.keys().filter(|n| !keep(n)).cloned().collect();
+        for node in &doomed {
+            let _ = self.remove_node(node);
+        }
+        doomed.len()
+    }
+
+    /// Keeps only the edges for which `keep(source, target, weight)` returns
+    /// `true`. Each undirected edge is tested once, in the orientation it was
+    /// added, and both of its entries are dropped together. Returns the
+    /// number of edges removed.
+    pub fn retain_edges<F: FnMut(&N, &N, &W) -> bool>(&mut self, mut keep: F) -> usize {
+        let doomed: Vec<EdgeId> = self.edges().filter(|e| !keep(e.source, e.target, &e.weight)).map(|e| e.id).collect();
+        for id in &doomed {
+            let _ = self.remove_edge_by_id(*id);
+        }
+        doomed.len()
+    }
+
+    /// Total weight of walking `path` edge by edge, taking the lightest
+    /// edge between consecutive nodes.
+    ///
+    /// Fails if a node is missing, a hop has no edge, or the sum overflows
+    /// `W`.
+    pub fn path_weight(&self, path: &[N]) -> Result<W, GraphError> {
+        if !path.iter().all(|n| self.contains_node(n)) {
+            return Err(GraphError::NodeNotFound);
+        }
+        let mut hops = Vec::with_capacity(path.len().saturating_sub(1));
+        for pair in path.windows(2) {
+            let lightest = self.neighbors(&pair[0])
+                .filter(|(v, _)| **v == pair[1])
+                .map(|(_, &w)| w)
+                .reduce(|a, b| if b < a { b } else { a })
+                .ok_or(GraphError::EdgeNotFound)?;
+            hops.push(lightest);
+        }
+        checked_sum(hops).ok_or(GraphError::WeightOverflow)
+    }
+
+
+    /// Copies the graph with the weights dropped. Nodes, edge ids, the
+    /// policy and the reverse index carry over. A `WeightedGraph` has no
+    /// payloads, so neither does the result: a `Graph::to_weighted` round
+    /// trip loses them.
+    pub fn to_unweighted(&self) -> Graph<N> {
+        let mut graph = Graph::with_policy(self.is_directed, self.policy);
+        if self.has_reverse_index() {
+            graph.enable_reverse_index();
+        }
+        for node in self.nodes() {
+            graph.add_node(node.clone());
+        }
+        for e in self.edges_in_id_order() {
+            graph.insert_edge_with_id(e.source.clone(), e.target.clone(), e.id);
+        }
+        graph
+    }
+
+    /// Copies the graph, replacing every weight with what `f` computes for
+    /// its edge. Edge ids carry over. Fails with `InvalidWeight` if `f`
+    /// returns an invalid weight.
+    pub fn map_weights<W2, F>(&self, mut f: F) -> Result<WeightedGraph<N, W2>, GraphError>
+    where
+        W2: Weight,
+        F: FnMut(EdgeRef<&N, W>) -> W2,
+    {
+        let mut mapped = WeightedGraph::with_policy(self.is_directed, self.policy);
+        if self.has_reverse_index() {
+            mapped.enable_reverse_index();
+        }
+        for node in self.nodes() {
+            mapped.add_node(node.clone());
+        }
+        for e in self.edges_in_id_order() {
+            let (u, v, id) = (e.source.clone(), e.target.clone(), e.id);
+            let w = f(e);
+            if !w.is_valid() {
+                return Err(GraphError::InvalidWeight);
+            }
+            mapped.insert_edge_with_id(u, v, w, id);
+        }
+        Ok(mapped)
+    }
+
+    /// Consumes the graph, returning its edges as `(u, v, weight)` tuples in
+    /// the order they were added, each undirected edge once. Isolated nodes
+    /// are dropped.
+    pub fn into_edges(self) -> Vec<(N, N, W)> {
+        let mut edges: Vec<_> = self.edge_table.into_iter().collect();
+        edges.sort_by_key(|(id, _)| *id);
+        edges.into_iter().map(|(_, edge)| edge).collect()
+    }
+
+    fn edges_in_id_order(&self) -> Vec<EdgeRef<&N, W>> {
+        let mut edges: Vec<_> = self.edges().collect();
+        edges.sort_by_key(|e| e.id);
+        edges
+    }
+
+    /// Checks the storage invariants `add_edge` maintains: every undirected
+    /// edge is stored under both endpoints, every target has an adjacency
+    /// list, and parallel edges only exist if the policy allows them.
+    /// Violations are reported in edge id order.
+    pub fn validate(&self) -> Result<(), Vec<Violation<N>>> {
+        integrity::validate(self)
+    }
+
+    /// Fixes everything `validate` reports: missing targets get an empty
+    /// adjacency list, undirected edges get their missing mirror entry, and
+    /// forbidden parallel edges are removed, keeping the oldest. Every fix
+    /// is recorded like any other change; a restored mirror shows up as the
+    /// edge being removed and added again under the same id. Returns the
+    /// violations that were repaired.
+    pub fn repair(&mut self) -> Vec<Violation<N>> {
+        integrity::repair(self)
+    }
+}
+
+impl<N: Eq + Hash + Clone, W: Weight> Storage<N> for WeightedGraph<N, W> {
+    fn is_directed(&self) -> bool {
+        self.is_directed
+    }
+
+    fn policy(&self) -> EdgePolicy {
+        self.policy
+    }
+
+    fn has_list(&self, node: &N) -> bool {
+        self.ajd_list.contains_key(node)
+    }
+
+    fn edge_endpoints<'a>(&'a self) -> impl Iterator<Item = (EdgeId, &'a N, &'a N)>
+    where
+        N: 'a,
+    {
+        self.edge_table.iter().map(|(id, (u, v, _))| (*id, u, v))
+    }
+
+    fn stored_ids(&self) -> impl Iterator<Item = EdgeId> {
+        self.ajd_list.values().flatten().map(|(_, _, id)| *id)
+    }
+
+    fn add_node(&mut self, node: N) -> bool {
+        WeightedGraph::add_node(self, node)
+    }
+
+    fn restore_edge(&mut self, id: EdgeId) {
+        let Some((u, v, weight)) = self.edge_table.remove(&id) else {
+            return;
+        };
+        remove_first(self.ajd_list.get_mut(&u), |(_, _, x)| *x == id);
+        remove_first(self.ajd_list.get_mut(&v), |(_, _, x)| *x == id);
+        let removed = EdgeRef { source: u.clone(), target: v.clone(), weight, id };
+        self.changes.record(|| Change::EdgeRemoved(removed));
+        self.insert_edge_with_id(u, v, weight, id);
+    }
+
+    fn remove_edge(&mut self, id: EdgeId) {
+        let _ = self.remove_edge_by_id(id);
+    }
+
+    fn rebuild_reverse_index(&mut self) {
+        if self.rev_list.is_some() {
+            self.rev_list = None;
+            self.enable_reverse_index();
+        }
+    }
+}
+/// Drops the weights, see `WeightedGraph::to_unweighted`.
+impl<N: Eq + Hash + Clone, W: Weight> From<&WeightedGraph<N, W>> for Graph<N> {
+    fn from(graph: &WeightedGraph<N, W>) -> Self {
+        graph.to_unweighted()
+    }
+}
+
+/// Adds every `(u, v, weight)` edge. Panics on the first edge with an
+/// invalid weight or that the graph's `EdgePolicy` rejects, leaving the
+/// edges before it added; call `add_edge` to handle errors instead.
+impl<N: Eq + Hash + Clone, W: Weight> Extend<(N, N, W)> for WeightedGraph<N, W> {
+    fn extend<I: IntoIterator<Item = (N, N, W)>>(&mut self, edges: I) {
+        for (u, v, w) in edges {
+            if let Err(err) = self.add_edge(u, v, w) {
+                panic!("cannot extend graph: {err}");
+            }
+        }
+    }
+}
+
+/// Collects into a directed graph. Panics on an invalid weight; use
+/// `WeightedGraph::from_edges` for an undirected graph or to get the error.
+impl<N: Eq + Hash + Clone, W: Weight> FromIterator<(N, N, W)> for WeightedGraph<N, W> {
+    fn from_iter<I: IntoIterator<Item = (N, N, W)>>(edges: I) -> Self {
+        let mut graph = WeightedGraph::new(true);
+        graph.extend(edges);
+        graph
+    }
+}
 
+/// Builds a directed graph from an array of edges, failing on the first
+/// invalid weight like `WeightedGraph::from_edges`.
+impl<N: Eq + Hash + Clone, W: Weight, const K: usize> TryFrom<[(N, N, W); K]> for WeightedGraph<N, W> {
+    type Error = GraphError;
+
+    fn try_from(edges: [(N, N, W); K]) -> Result<Self, GraphError> {
+        WeightedGraph::from_edges(true, edges)
+    }
+}
+
+/// Overwrites the weight of every entry for edge `id` in an adjacency list.
+fn set_entry_weight<N, W: Copy>(list: Option<&mut Vec<(N, W, EdgeId)>>, id: EdgeId, weight: W) {
+    for entry in list.into_iter().flatten() {
+        if entry.2 == id {
+            entry.1 = weight;
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::changelog::Change;
+
+    #[test]
+    fn test_remove_edge_returns_weight() {
+        let mut graph = WeightedGraph::new(false);
+        graph.add_edge(1, 2, 5).unwrap();
+        graph.add_edge(1, 2, 9).unwrap();
+
+        assert_eq!(graph.remove_edge(&2, &1), Ok(5));
+        assert_eq!(graph.neighbors(&1).collect::<Vec<_>>(), vec![(&2, &9)]);
+        assert_eq!(graph.neighbors(&2).collect::<Vec<_>>(), vec![(&1, &9)]);
+        assert_eq!(graph.remove_edge(&1, &3), Err(GraphError::NodeNotFound));
+        graph.add_node(3);
+        assert_eq!(graph.remove_edge(&1, &3), Err(GraphError::EdgeNotFound));
+    }
+
+    #[test]
+    fn test_remove_node_and_retain() {
+        let mut graph = WeightedGraph::new(false);
+        graph.add_edge("a", "b", 1.0).unwrap();
+        graph.add_edge("b", "c", 2.0).unwrap();
+        graph.add_edge("c", "a", 3.0).unwrap();
+        graph.add_edge("c", "c", 4.0).unwrap();
+
+        assert_eq!(graph.retain_edges(|_, _, &w| w < 2.5), 2);
+        assert_eq!(graph.neighbors(&"c").collect::<Vec<_>>(), vec![(&"b", &2.0)]);
+        assert_eq!(graph.neighbors(&"a").collect::<Vec<_>>(), vec![(&"b", &1.0)]);
+
+        assert_eq!(graph.remove_node(&"b"), Ok(2));
+        assert_eq!(graph.edge_count(), 0);
+        assert_eq!(graph.retain_nodes(|&n| n == "a"), 1);
+        assert_eq!(graph.clear_edges(), 0);
+    }
+
+    #[test]
+    fn test_nodes_degrees_and_edges() {
+        let mut graph = WeightedGraph::new(false);
+        graph.add_edge(1, 2, 10).unwrap();
+        graph.add_edge(2, 3, 20).unwrap();
+        graph.add_edge(3, 3, 30).unwrap();
+        graph.add_node(4);
+
+        assert_eq!(graph.node_count(), 4);
+        assert_eq!(graph.edge_count(), 3);
+        assert!(graph.has_edge(&3, &2));
+        assert!(graph.contains_node(&4));
+        assert_eq!(graph.degree(&3), Ok(3));
+        assert_eq!(graph.degree(&4), Ok(0));
+        assert_eq!(graph.neighbors(&1).collect::<Vec<_>>(), vec![(&2, &10)]);
+
+        let mut weights: Vec<_> = graph.edges().map(|e| e.weight).collect();
+        weights.sort();
+        assert_eq!(weights, vec![10, 20, 30]);
+
+        let mut directed = WeightedGraph::new(true);
+        directed.add_edge("x", "y", 1).unwrap();
+        assert_eq!(directed.node_count(), 2);
+        assert_eq!(directed.in_degree(&"y"), Ok(1));
+        assert_eq!(directed.out_degree(&"y"), Ok(0));
+    }
+
+    #[test]
+    fn test_reverse_index() {
+        let mut graph = WeightedGraph::new(true);
+        graph.enable_reverse_index();
+        graph.add_edge("a", "c", 1).unwrap();
+        graph.add_edge("b", "c", 2).unwrap();
+        graph.add_edge("c", "d", 3).unwrap();
+
+        let mut preds: Vec<_> = graph.predecessors(&"c").map(|(&u, &w)| (u, w)).collect();
+        preds.sort();
+        assert_eq!(preds, vec![("a", 1), ("b", 2)]);
+
+        assert_eq!(graph.retain_edges(|_, _, &w| w != 2), 1);
+        assert_eq!(graph.in_degree(&"c"), Ok(1));
+        assert_eq!(graph.remove_edge(&"a", &"c"), Ok(1));
+        assert_eq!(graph.in_degree(&"c"), Ok(0));
+        graph.add_edge("d", "c", 4).unwrap();
+        assert_eq!(graph.remove_node(&"c"), Ok(2));
+        assert_eq!(graph.out_degree(&"d"), Ok(0));
+    }
+
+    #[test]
+    fn test_edge_ids_and_set_weight() {
+        let mut graph = WeightedGraph::new(false);
+        let first = graph.add_edge(1, 2, 5).unwrap();
+        let second = graph.add_edge(1, 2, 7).unwrap();
+        assert_ne!(first, second);
+
+        assert_eq!(graph.set_weight(second, 3), Ok(7));
+        assert_eq!(graph.weight(second), Some(3));
+        // both stored directions see the new weight
+        let back: Vec<_> = graph.edges_connecting(&2, &1).map(|e| (e.id, e.weight)).collect();
+        assert_eq!(back, vec![(first, 5), (second, 3)]);
+
+        let removed = graph.remove_edge_by_id(first).unwrap();
+        assert_eq!((removed.source, removed.target, removed.weight), (1, 2, 5));
+        assert!(!graph.contains_edge(first));
+        assert_eq!(graph.edge(second).map(|e| e.cloned()), Some(EdgeRef { source: 1, target: 2, weight: 3, id: second }));
+        assert_eq!(graph.set_weight(first, 1), Err(GraphError::EdgeNotFound));
+
+        let third = graph.add_edge(2, 3, 1).unwrap();
+        assert!(third.index() > second.index());
+    }
+
+    #[test]
+    fn test_set_weight_updates_reverse_index() {
+        let mut graph = WeightedGraph::new(true);
+        graph.enable_reverse_index();
+        let id = graph.add_edge("a", "b", 1.0).unwrap();
+        graph.set_weight(id, 2.5).unwrap();
 
+        assert_eq!(graph.predecessors(&"b").collect::<Vec<_>>(), vec![(&"a", &2.5)]);
+        assert_eq!(graph.path_weight(&["a", "b"]), Ok(2.5));
+    }
+
+    #[test]
+    fn test_invalid_weights_and_paths() {
+        let mut graph: WeightedGraph<&str, f64> = WeightedGraph::new(true);
+        let id = graph.add_edge("a", "b", 1.0).unwrap();
+        graph.add_edge("b", "c", f64::MAX).unwrap();
+
+        assert_eq!(graph.add_edge("a", "c", f64::NAN), Err(GraphError::InvalidWeight));
+        assert!(!graph.has_edge(&"a", &"c"));
+        assert_eq!(graph.set_weight(id, f64::NAN), Err(GraphError::InvalidWeight));
+        assert_eq!(graph.weight(id), Some(1.0));
+
+        assert_eq!(graph.path_weight(&["a", "z"]), Err(GraphError::NodeNotFound));
+        assert_eq!(graph.path_weight(&["a", "c"]), Err(GraphError::EdgeNotFound));
+        graph.add_edge("c", "d", f64::MAX).unwrap();
+        assert_eq!(graph.path_weight(&["b", "c", "d"]), Err(GraphError::WeightOverflow));
+        assert_eq!(graph.path_weight(&["a"]), Ok(0.0));
+    }
+
+    #[test]
+    fn test_simple_policy() {
+        let mut graph = WeightedGraph::with_policy(true, EdgePolicy::SIMPLE);
+        graph.add_edge(1, 2, 1.0).unwrap();
+
+        assert_eq!(graph.add_edge(1, 2, 2.0), Err(GraphError::DuplicateEdge));
+        assert_eq!(graph.add_edge(2, 2, 2.0), Err(GraphError::SelfLoop));
+        graph.add_edge(2, 1, 3.0).unwrap();
+        assert_eq!(graph.edge_count(), 2);
+    }
+
+    #[test]
+    fn test_remove_node_directed() {
+        let mut graph = WeightedGraph::new(true);
+        graph.add_edge(1, 2, 1u32).unwrap();
+        graph.add_edge(2, 3, 1).unwrap();
+        graph.add_edge(3, 1, 1).unwrap();
+
+        assert_eq!(graph.remove_node(&1), Ok(2));
+        assert_eq!(graph.out_degree(&3), Ok(0));
+        assert_eq!(graph.neighbors(&2).collect::<Vec<_>>(), vec![(&3, &1)]);
+    }
+
+    #[test]
+    fn test_validate_and_repair() {
+        let paired = HashMap::from([("a", vec![("b", 1)]), ("b", vec![("a", 1)])]);
+        let graph = WeightedGraph::from_adjacency_unchecked(false, EdgePolicy::default(), paired);
+        assert_eq!(graph.validate(), Ok(()));
+        assert_eq!(graph.edge_count(), 1);
+
+        // mirror entries with different weights can't be paired
+        let skewed = HashMap::from([("a", vec![("b", 1)]), ("b", vec![("a", 2), ("c", 4)])]);
+        let mut graph = WeightedGraph::from_adjacency_unchecked(false, EdgePolicy::SIMPLE, skewed);
+        let violations = graph.validate().unwrap_err();
+        assert_eq!(violations.iter().filter(|v| matches!(v, Violation::Asymmetric { .. })).count(), 3);
+        assert_eq!(violations.iter().filter(|v| matches!(v, Violation::DanglingTarget { target: "c", .. })).count(), 1);
+        assert_eq!(violations.iter().filter(|v| matches!(v, Violation::DuplicateEdge { .. })).count(), 1);
+
+        graph.repair();
+        assert_eq!(graph.validate(), Ok(()));
+        assert_eq!(graph.edge_count(), 2);
+        assert_eq!(graph.neighbors(&"c").collect::<Vec<_>>(), vec![(&"b", &4)]);
+        assert_eq!(graph.neighbors(&"a").count(), 1);
+        assert_eq!(graph.neighbors(&"b").count(), 2);
+    }
+
+    #[test]
+    fn test_edge_tuple_conversions() {
+        let graph = WeightedGraph::try_from([("a", "b", 1.5), ("b", "c", 2.0)]).unwrap();
+        assert!(graph.is_directed());
+        assert_eq!(graph.edge_count(), 2);
+        let nan = WeightedGraph::try_from([("a", "b", 1.5), ("b", "c", f64::NAN)]);
+        assert_eq!(nan.unwrap_err(), GraphError::InvalidWeight);
+        assert_eq!(WeightedGraph::from_edges(false, [(1, 2, f64::NAN)]).unwrap_err(), GraphError::InvalidWeight);
+
+        let mut undirected = WeightedGraph::from_edges(false, [(1, 2, 5u32), (2, 3, 6)]).unwrap();
+        undirected.extend([(3, 1, 7)]);
+        assert_eq!(undirected.clone().into_edges(), vec![(1, 2, 5), (2, 3, 6), (3, 1, 7)]);
+
+        let collected: WeightedGraph<u8, u8> = (0..3).map(|i| (i, i + 1, i)).collect();
+        assert_eq!(collected.into_edges(), vec![(0, 1, 0), (1, 2, 1), (2, 3, 2)]);
+        let rejected = std::panic::catch_unwind(|| [(1, 2, f64::NAN)].into_iter().collect::<WeightedGraph<i32, f64>>());
+        assert!(rejected.is_err());
+    }
+
+    #[test]
+    fn test_to_unweighted_and_map_weights() {
+        let mut graph = WeightedGraph::with_policy(true, EdgePolicy::SIMPLE);
+        graph.enable_reverse_index();
+        let ab = graph.add_edge("a", "b", 3).unwrap();
+        graph.add_edge("b", "c", 4).unwrap();
+        graph.add_node("d");
+
+        let plain = Graph::from(&graph);
+        assert_eq!(plain.node_count(), 4);
+        assert!(plain.has_reverse_index());
+        assert_eq!(plain.policy(), EdgePolicy::SIMPLE);
+        assert_eq!(plain.edge(ab).map(|e| (*e.source, *e.target)), Some(("a", "b")));
+
+        let scaled = graph.map_weights(|e| f64::from(e.weight) / 2.0).unwrap();
+        assert_eq!(scaled.weight(ab), Some(1.5));
+        assert_eq!(scaled.predecessors(&"c").collect::<Vec<_>>(), vec![(&"b", &2.0)]);
+        assert_eq!(graph.map_weights(|_| f32::NAN).unwrap_err(), GraphError::InvalidWeight);
+    }
+
+    #[test]
+    fn test_change_log_weights() {
+        let mut graph = WeightedGraph::new(true);
+        let ab = graph.add_edge("a", "b", 2).unwrap();
+        graph.enable_change_log();
+        let start = graph.version();
+
+        graph.set_weight(ab, 5).unwrap();
+        assert_eq!(graph.set_weight(ab, -1).unwrap(), 5);
+        graph.remove_edge(&"a", &"b").unwrap();
+        assert_eq!(graph.set_weight(ab, 1), Err(GraphError::EdgeNotFound));
+
+        let changes: Vec<_> = graph.change_log_mut().drain_since(start).unwrap().into_iter().map(|d| d.change).collect();
+        assert_eq!(changes, [
+            Change::WeightChanged { id: ab, old: 2, new: 5 },
+            Change::WeightChanged { id: ab, old: 5, new: -1 },
+            Change::EdgeRemoved(EdgeRef { source: "a", target: "b", weight: -1, id: ab }),
+        ]);
+        graph.disable_change_log();
+        assert_eq!(graph.version(), start + 3);
+        assert!(graph.change_log().since(start + 3).is_none());
+    }
 }
@@ -1,6 +1,3 @@
-mod core;
-mod algos;
-
 fn main() {
     println!("Hello, world!");
 }